@@ -5,27 +5,85 @@
 //! Configuration for the greeter
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use gtk4::gdk::RGBA;
 use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+use tracing::{info, warn};
 
-use crate::constants::{GREETING_MSG, POWEROFF_CMD, REBOOT_CMD};
+use crate::constants::{
+    BANNER_ACCEPT_LABEL, BOOT_ENTRIES_DIR, BOOT_ENTRY_CMD, DATE_FMT, FIRMWARE_SETUP_CMD,
+    GREETING_MSG, HIBERNATE_CMD, IDLE_WARNING_SECS, POWEROFF_CMD, REBOOT_CMD, SUSPEND_CMD,
+    THEME_DIRS, TIME_FMT_12H, TIME_FMT_24H,
+};
 use crate::tomlutils::load_toml;
 
+/// Name of the partial config in a theme bundle
+const THEME_CONFIG_FILE: &str = "theme.toml";
+/// Name of the stylesheet in a theme bundle
+const THEME_CSS_FILE: &str = "style.css";
+/// Sections and keys of paths in the config of a theme bundle, which are relative to the bundle
+const THEME_PATH_KEYS: [(&str, &str); 2] = [("background", "path"), ("background", "monitors")];
+/// CSS functions accepted as gradients for the background fallback
+const GRADIENT_FUNCTIONS: [&str; 3] = ["linear-gradient(", "radial-gradient(", "conic-gradient("];
+
 #[derive(Deserialize, Serialize)]
 pub struct AppearanceSettings {
+    /// Greeting message, which can contain placeholders such as `{user}`
     #[serde(default = "default_greeting_msg")]
     pub greeting_msg: String,
+    /// Greetings for different times of the day, used by the `{greeting}` placeholder
+    #[serde(default)]
+    pub time_greetings: Vec<TimeGreeting>,
+    /// Colour of the clock, the buttons and the outlines of focused widgets
+    #[serde(default)]
+    pub accent_color: Option<String>,
+    /// Colour of the text
+    #[serde(default)]
+    pub foreground_color: Option<String>,
+    /// Background colour of the password entry
+    #[serde(default)]
+    pub entry_background: Option<String>,
+    /// Radius (in pixels) of the corners of entries, buttons and panels
+    #[serde(default)]
+    pub corner_radius: Option<u32>,
+    /// Font family of the clock
+    #[serde(default)]
+    pub clock_font: Option<String>,
+    /// Font size (in pixels) of the time in the clock
+    #[serde(default)]
+    pub clock_font_size: Option<u32>,
+    /// Opacity (from 0 to 1) of the dark background behind the login UI and the issue panel
+    #[serde(default)]
+    pub panel_opacity: Option<f64>,
 }
 
 impl Default for AppearanceSettings {
     fn default() -> Self {
         AppearanceSettings {
             greeting_msg: default_greeting_msg(),
+            time_greetings: Vec::new(),
+            accent_color: None,
+            foreground_color: None,
+            entry_background: None,
+            corner_radius: None,
+            clock_font: None,
+            clock_font_size: None,
+            panel_opacity: None,
         }
     }
 }
 
+/// Struct for a greeting used from some hour of the day
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TimeGreeting {
+    /// Hour of the day (0-23) from which the greeting is used, until the next greeting starts
+    pub start_hour: u32,
+    /// The greeting (eg. "Good morning")
+    pub text: String,
+}
+
 /// Struct holding all supported GTK settings
 #[derive(Default, Deserialize, Serialize)]
 pub struct GtkSettings {
@@ -51,22 +109,101 @@ pub enum BgFit {
     ScaleDown,
 }
 
+/// Path to a background image or a directory of them, or a list of such paths
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum BgPaths {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// How the background image is chosen, when there are multiple images
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+pub enum BgOrder {
+    #[default]
+    Random,
+    Sequential,
+}
+
 /// Struct for info about the background image
 #[derive(Default, Deserialize, Serialize)]
 struct Background {
     #[serde(default)]
-    path: Option<String>,
+    path: Option<BgPaths>,
+    #[serde(default)]
+    order: BgOrder,
+    /// Time (in seconds) after which the next background image is shown
+    #[serde(default)]
+    interval: Option<u64>,
+    /// Colour or gradient shown where no background image is shown
+    #[serde(default)]
+    fallback: Option<String>,
+    /// Background images for specific monitors, by their connector names
+    #[serde(default)]
+    monitors: HashMap<String, String>,
     #[serde(default)]
     fit: BgFit,
 }
 
-/// Struct for reboot/poweroff commands
+/// Struct for settings related to the users shown in the greeter
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct UserSettings {
+    /// Directory containing avatar images named after users
+    #[serde(default)]
+    pub avatar_dir: Option<String>,
+}
+
+/// Struct for settings related to finding X11/Wayland sessions
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct SessionSettings {
+    /// Directories to search for session desktop files, before the default ones
+    #[serde(default)]
+    pub extra_dirs: Vec<String>,
+    /// Glob patterns for paths of session desktop files that should be hidden
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// Struct for settings related to the monitors that the greeter is shown on
+#[derive(Default, Deserialize, Serialize)]
+pub struct MonitorSettings {
+    /// Connector name of the monitor that shows the login UI (eg. "DP-1")
+    #[serde(default)]
+    pub primary: Option<String>,
+    /// Whether the login UI moves to the monitor where the pointer moved or a key was pressed
+    #[serde(default)]
+    pub follow_pointer: bool,
+}
+
+/// Struct for an action that ends the greeter (eg. Reboot), shown as a button
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PowerAction {
+    /// Text shown on the button
+    pub label: String,
+    /// Name of the icon shown on the button
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Command run when the button is clicked
+    pub command: Vec<String>,
+    /// Whether to ask the user before running the command
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+/// Struct for commands that end the greeter
 #[derive(Deserialize, Serialize)]
 pub struct SystemCommands {
     #[serde(default = "default_reboot_command")]
     pub reboot: Vec<String>,
     #[serde(default = "default_poweroff_command")]
     pub poweroff: Vec<String>,
+    #[serde(default = "default_suspend_command")]
+    pub suspend: Vec<String>,
+    #[serde(default = "default_hibernate_command")]
+    pub hibernate: Vec<String>,
+    /// Actions shown as buttons, instead of the default ones using the commands above
+    #[serde(default)]
+    actions: Option<Vec<PowerAction>>,
 }
 
 impl Default for SystemCommands {
@@ -74,7 +211,42 @@ impl Default for SystemCommands {
         SystemCommands {
             reboot: default_reboot_command(),
             poweroff: default_poweroff_command(),
+            suspend: default_suspend_command(),
+            hibernate: default_hibernate_command(),
+            actions: None,
+        }
+    }
+}
+
+impl SystemCommands {
+    /// Get the command for a power action run when the greeter is idle.
+    pub fn get_idle_command(&self, action: IdleAction) -> &[String] {
+        match action {
+            IdleAction::Reboot => &self.reboot,
+            IdleAction::PowerOff => &self.poweroff,
+            IdleAction::Suspend => &self.suspend,
+            IdleAction::Hibernate => &self.hibernate,
+        }
+    }
+
+    /// Get the actions shown as buttons.
+    pub fn get_actions(&self) -> Vec<PowerAction> {
+        if let Some(actions) = &self.actions {
+            return actions.clone();
         }
+
+        let action = |label: &str, command: &Vec<String>, confirm| PowerAction {
+            label: label.to_string(),
+            icon: None,
+            command: command.clone(),
+            confirm,
+        };
+        vec![
+            action("Reboot", &self.reboot, true),
+            action("Power Off", &self.poweroff, true),
+            action("Suspend", &self.suspend, false),
+            action("Hibernate", &self.hibernate, false),
+        ]
     }
 }
 
@@ -86,13 +258,420 @@ fn default_poweroff_command() -> Vec<String> {
     shlex::split(POWEROFF_CMD).expect("Unable to lex poweroff command")
 }
 
+fn default_suspend_command() -> Vec<String> {
+    shlex::split(SUSPEND_CMD).expect("Unable to lex suspend command")
+}
+
+fn default_hibernate_command() -> Vec<String> {
+    shlex::split(HIBERNATE_CMD).expect("Unable to lex hibernate command")
+}
+
+/// Power action run by the greeter when it's idle, using the commands in [`SystemCommands`]
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+pub enum IdleAction {
+    Reboot,
+    PowerOff,
+    #[default]
+    Suspend,
+    Hibernate,
+}
+
+/// Struct for settings related to running a power action when nobody uses the greeter
+#[derive(Deserialize, Serialize)]
+pub struct IdleSettings {
+    /// Time (in seconds) without any input, after which the power action is run
+    ///
+    /// If missing, the greeter never runs a power action by itself.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// Power action that is run
+    #[serde(default)]
+    pub action: IdleAction,
+    /// Time (in seconds) before running the power action, for which a warning is shown
+    #[serde(default = "default_idle_warning")]
+    pub warning: u64,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        IdleSettings {
+            timeout: None,
+            action: IdleAction::default(),
+            warning: default_idle_warning(),
+        }
+    }
+}
+
+fn default_idle_warning() -> u64 {
+    IDLE_WARNING_SECS
+}
+
+/// Struct for settings related to dimming and blanking the screen when nobody uses the greeter
+#[derive(Default, Deserialize, Serialize)]
+pub struct ScreensaverSettings {
+    /// Time (in seconds) without any input, after which the screen is dimmed
+    #[serde(default)]
+    pub dim_timeout: Option<u64>,
+    /// Time (in seconds) without any input, after which the screen is blanked
+    #[serde(default)]
+    pub blank_timeout: Option<u64>,
+    /// Whether the clock is shown on the blank screen
+    #[serde(default)]
+    pub show_clock: bool,
+}
+
+/// Struct for settings related to the clock
+#[derive(Deserialize, Serialize)]
+pub struct ClockSettings {
+    /// Whether the clock is shown
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Format of the date, as per `chrono::format::strftime`
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    /// Format of the time, as per `chrono::format::strftime`
+    ///
+    /// If missing, the hours and minutes are shown as per `twelve_hour`.
+    #[serde(default)]
+    pub time_format: Option<String>,
+    /// Whether the default time format uses the 12-hour format instead of the 24-hour one
+    #[serde(default)]
+    pub twelve_hour: bool,
+    /// Names of extra time zones (eg. "America/New_York") whose time is shown
+    #[serde(default)]
+    pub timezones: Vec<String>,
+}
+
+impl Default for ClockSettings {
+    fn default() -> Self {
+        ClockSettings {
+            enabled: true,
+            date_format: default_date_format(),
+            time_format: None,
+            twelve_hour: false,
+            timezones: Vec::new(),
+        }
+    }
+}
+
+impl ClockSettings {
+    /// Get the format of the time, as configured or per `twelve_hour`.
+    pub fn get_time_format(&self) -> &str {
+        match &self.time_format {
+            Some(time_format) => time_format,
+            None if self.twelve_hour => TIME_FMT_12H,
+            None => TIME_FMT_24H,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_date_format() -> String {
+    DATE_FMT.to_string()
+}
+
+/// Struct for settings related to rebooting into a boot entry or the firmware setup
+#[derive(Deserialize, Serialize)]
+pub struct BootSettings {
+    /// Directory containing Boot Loader Specification entries
+    #[serde(default = "default_boot_entries_dir")]
+    pub entries_dir: String,
+    /// Command for rebooting into a boot entry, where `{id}` is replaced by the entry's ID
+    #[serde(default = "default_boot_entry_command")]
+    pub entry_command: Vec<String>,
+    /// Command for rebooting into the firmware setup
+    #[serde(default = "default_firmware_setup_command")]
+    pub firmware_command: Vec<String>,
+}
+
+impl Default for BootSettings {
+    fn default() -> Self {
+        BootSettings {
+            entries_dir: default_boot_entries_dir(),
+            entry_command: default_boot_entry_command(),
+            firmware_command: default_firmware_setup_command(),
+        }
+    }
+}
+
+fn default_boot_entries_dir() -> String {
+    BOOT_ENTRIES_DIR.to_string()
+}
+
+fn default_boot_entry_command() -> Vec<String> {
+    shlex::split(BOOT_ENTRY_CMD).expect("Unable to lex boot entry command")
+}
+
+fn default_firmware_setup_command() -> Vec<String> {
+    shlex::split(FIRMWARE_SETUP_CMD).expect("Unable to lex firmware setup command")
+}
+
+/// Struct for settings related to showing an issue file (eg. `/etc/issue`)
+#[derive(Default, Deserialize, Serialize)]
+pub struct IssueSettings {
+    /// Path to the issue file, whose agetty-style escapes are expanded
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// Analogue to `gtk4::Align`
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub enum LayoutAlign {
+    Start,
+    Center,
+    End,
+    Fill,
+}
+
+/// Position of a block of the UI (eg. the clock), and whether it's hidden
+///
+/// Missing settings are taken from the layout preset, or from the stylesheet for margins.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct BlockLayout {
+    /// Horizontal alignment on the screen
+    #[serde(default)]
+    pub halign: Option<LayoutAlign>,
+    /// Vertical alignment on the screen
+    #[serde(default)]
+    pub valign: Option<LayoutAlign>,
+    /// Margin (in pixels) from the top edge
+    #[serde(default)]
+    pub margin_top: Option<u32>,
+    /// Margin (in pixels) from the bottom edge
+    #[serde(default)]
+    pub margin_bottom: Option<u32>,
+    /// Margin (in pixels) from the left edge
+    #[serde(default)]
+    pub margin_left: Option<u32>,
+    /// Margin (in pixels) from the right edge
+    #[serde(default)]
+    pub margin_right: Option<u32>,
+    /// Whether the block is hidden
+    #[serde(default)]
+    pub hidden: Option<bool>,
+}
+
+impl BlockLayout {
+    /// Use the settings of the other layout for the settings missing in this one.
+    fn or(self, other: Self) -> Self {
+        BlockLayout {
+            halign: self.halign.or(other.halign),
+            valign: self.valign.or(other.valign),
+            margin_top: self.margin_top.or(other.margin_top),
+            margin_bottom: self.margin_bottom.or(other.margin_bottom),
+            margin_left: self.margin_left.or(other.margin_left),
+            margin_right: self.margin_right.or(other.margin_right),
+            hidden: self.hidden.or(other.hidden),
+        }
+    }
+}
+
+/// Preset positions for the blocks of the UI
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+pub enum LayoutPreset {
+    /// Clock at the top, with the login panel and the power action buttons at the bottom
+    #[default]
+    Default,
+    /// Login panel in a card at the center, for screens of any aspect ratio
+    CenteredCard,
+    /// Login panel and power action buttons in a sidebar on the left, with the clock at the top
+    /// right, for wide screens
+    LeftSidebar,
+    /// Only the login panel at the center, with the power action buttons at the bottom right
+    Minimal,
+}
+
+impl LayoutPreset {
+    /// Get the layouts of the clock, the login panel and the power action buttons.
+    ///
+    /// These only have the alignments and the hidden blocks, since the margins of each preset are
+    /// in the default stylesheet, where themes can change them.
+    fn get_layouts(self) -> [BlockLayout; 3] {
+        let at = |halign, valign| BlockLayout {
+            halign: Some(halign),
+            valign: Some(valign),
+            ..BlockLayout::default()
+        };
+        match self {
+            // The default stylesheet and template already have this layout.
+            Self::Default => Default::default(),
+            Self::CenteredCard => [
+                at(LayoutAlign::Center, LayoutAlign::Start),
+                at(LayoutAlign::Center, LayoutAlign::Center),
+                at(LayoutAlign::Center, LayoutAlign::End),
+            ],
+            Self::LeftSidebar => [
+                at(LayoutAlign::End, LayoutAlign::Start),
+                at(LayoutAlign::Start, LayoutAlign::Fill),
+                at(LayoutAlign::Start, LayoutAlign::End),
+            ],
+            Self::Minimal => [
+                BlockLayout {
+                    hidden: Some(true),
+                    ..BlockLayout::default()
+                },
+                at(LayoutAlign::Center, LayoutAlign::Center),
+                at(LayoutAlign::End, LayoutAlign::End),
+            ],
+        }
+    }
+}
+
+/// Struct for settings related to the positions of the blocks of the UI
+#[derive(Default, Deserialize, Serialize)]
+pub struct LayoutSettings {
+    /// Preset positions, which are changed by the settings of each block
+    #[serde(default)]
+    pub preset: LayoutPreset,
+    /// Position of the clock
+    #[serde(default)]
+    clock: BlockLayout,
+    /// Position of the login panel
+    #[serde(default)]
+    login: BlockLayout,
+    /// Position of the power action buttons
+    #[serde(default)]
+    power: BlockLayout,
+}
+
+impl LayoutSettings {
+    /// Get the layouts of the clock, the login panel and the power action buttons, after applying
+    /// the preset.
+    pub fn get_layouts(&self) -> [BlockLayout; 3] {
+        let [clock, login, power] = self.preset.get_layouts();
+        [
+            self.clock.clone().or(clock),
+            self.login.clone().or(login),
+            self.power.clone().or(power),
+        ]
+    }
+}
+
+/// Struct for settings related to the legal banner that must be accepted before logging in
+#[derive(Deserialize, Serialize)]
+pub struct BannerSettings {
+    /// Text of the banner
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Path to a file containing the text of the banner, which is used instead of the text
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Label of the button that accepts the banner
+    #[serde(default = "default_banner_accept_label")]
+    pub accept_label: String,
+}
+
+impl Default for BannerSettings {
+    fn default() -> Self {
+        BannerSettings {
+            text: None,
+            path: None,
+            accept_label: default_banner_accept_label(),
+        }
+    }
+}
+
+fn default_banner_accept_label() -> String {
+    BANNER_ACCEPT_LABEL.to_string()
+}
+
+/// Check whether a colour in the config can be used in CSS, warning if it can't.
+fn is_valid_color(key: &str, color: &str) -> bool {
+    let is_valid = RGBA::parse(color).is_ok();
+    if !is_valid {
+        warn!("Ignoring invalid colour for '{key}' in the config: '{color}'");
+    }
+    is_valid
+}
+
 fn default_greeting_msg() -> String {
     GREETING_MSG.to_string()
 }
 
+/// Find the directory of the theme bundle with the given name.
+fn find_theme_dir(name: &str) -> Option<PathBuf> {
+    // Don't let the name escape the theme directories.
+    if name.is_empty() || name.starts_with('.') || name.contains('/') {
+        warn!("Invalid theme name: '{name}'");
+        return None;
+    }
+    let dir = THEME_DIRS
+        .split(':')
+        .map(|dir| Path::new(dir).join(name))
+        .find(|dir| dir.is_dir());
+    if dir.is_none() {
+        warn!("Theme '{name}' not found in any of: {THEME_DIRS}");
+    }
+    dir
+}
+
+/// Load the partial config of a theme bundle, with its paths made absolute.
+fn load_theme_config(theme_dir: &Path) -> Table {
+    let path = theme_dir.join(THEME_CONFIG_FILE);
+    if !path.exists() {
+        // The theme might only have a stylesheet.
+        return Table::new();
+    }
+    let mut config: Table = load_toml(&path);
+    // A theme can't choose another theme.
+    config.remove("theme");
+
+    for (section, key) in THEME_PATH_KEYS {
+        if let Some(value) = config
+            .get_mut(section)
+            .and_then(Value::as_table_mut)
+            .and_then(|section| section.get_mut(key))
+        {
+            make_theme_paths_absolute(value, theme_dir);
+        }
+    }
+    config
+}
+
+/// Make the paths in the config of a theme bundle absolute, including those in lists and tables.
+fn make_theme_paths_absolute(value: &mut Value, theme_dir: &Path) {
+    match value {
+        Value::String(path) => {
+            *path = theme_dir.join(path.as_str()).to_string_lossy().into_owned();
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| make_theme_paths_absolute(value, theme_dir)),
+        Value::Table(table) => table
+            .iter_mut()
+            .for_each(|(_, value)| make_theme_paths_absolute(value, theme_dir)),
+        _ => (),
+    }
+}
+
+/// Merge the settings of the overlay into the base config, replacing any settings in both.
+///
+/// Sections are merged recursively, so that the overlay can change only some keys in a section.
+fn merge_config(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_section)), Value::Table(section)) => {
+                merge_config(base_section, section)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 /// The configuration struct
 #[derive(Default, Deserialize, Serialize)]
 pub struct Config {
+    /// Name of the theme bundle whose settings are used, unless set in the config
+    #[serde(default)]
+    theme: Option<String>,
+    /// Directory of the chosen theme bundle
+    #[serde(skip)]
+    theme_dir: Option<PathBuf>,
     #[serde(default)]
     appearance: AppearanceSettings,
     #[serde(default)]
@@ -103,19 +682,163 @@ pub struct Config {
     gtk: Option<GtkSettings>,
     #[serde(default)]
     commands: SystemCommands,
+    #[serde(default)]
+    users: UserSettings,
+    #[serde(default)]
+    sessions: SessionSettings,
+    #[serde(default)]
+    monitors: MonitorSettings,
+    #[serde(default)]
+    boot: BootSettings,
+    #[serde(default)]
+    idle: IdleSettings,
+    #[serde(default)]
+    screensaver: ScreensaverSettings,
+    #[serde(default)]
+    clock: ClockSettings,
+    #[serde(default)]
+    issue: IssueSettings,
+    #[serde(default)]
+    banner: BannerSettings,
+    #[serde(default)]
+    layout: LayoutSettings,
 }
 
 impl Config {
+    /// Load the config, along with the theme bundle chosen in it.
+    ///
+    /// The settings of the theme are used for those that aren't explicitly set in the config.
     pub fn new(path: &Path) -> Self {
-        load_toml(path)
+        let table: Table = load_toml(path);
+        let theme_dir = table
+            .get("theme")
+            .and_then(Value::as_str)
+            .and_then(find_theme_dir);
+
+        let mut config = if let Some(theme_dir) = &theme_dir {
+            info!("Using theme: {}", theme_dir.display());
+            let mut merged = load_theme_config(theme_dir);
+            merge_config(&mut merged, table.clone());
+            Value::Table(merged).try_into().unwrap_or_else(|err| {
+                warn!(
+                    "Ignoring invalid config of theme '{}': {err}",
+                    theme_dir.display()
+                );
+                Self::from_table(table)
+            })
+        } else {
+            Self::from_table(table)
+        };
+        config.theme_dir = theme_dir;
+        config.drop_invalid_settings();
+        config
+    }
+
+    /// Remove the settings that are used in the generated CSS, if their values are invalid.
+    ///
+    /// This warns about each invalid setting, so that the GUI can use the rest as they are.
+    fn drop_invalid_settings(&mut self) {
+        let appearance = &mut self.appearance;
+        for (key, color) in [
+            ("appearance.accent_color", &mut appearance.accent_color),
+            (
+                "appearance.foreground_color",
+                &mut appearance.foreground_color,
+            ),
+            (
+                "appearance.entry_background",
+                &mut appearance.entry_background,
+            ),
+        ] {
+            *color = color.take().filter(|color| is_valid_color(key, color));
+        }
+
+        appearance.panel_opacity = appearance.panel_opacity.filter(|opacity| {
+            let is_valid = (0.0..=1.0).contains(opacity);
+            if !is_valid {
+                warn!(
+                    "Ignoring 'appearance.panel_opacity' outside 0 to 1 in the config: {opacity}"
+                );
+            }
+            is_valid
+        });
+        appearance.clock_font = appearance.clock_font.take().filter(|font| {
+            // The font is quoted in the CSS, so it can't contain characters that end the quotes.
+            let is_valid = !font.contains(['"', '\\', ';', '{', '}']);
+            if !is_valid {
+                warn!("Ignoring invalid 'appearance.clock_font' in the config: '{font}'");
+            }
+            is_valid
+        });
+        appearance.clock_font_size = appearance.clock_font_size.filter(|&size| {
+            if size == 0 {
+                warn!("Ignoring 'appearance.clock_font_size' of zero in the config");
+            }
+            size != 0
+        });
+
+        self.background.fallback = self.background.fallback.take().filter(|fallback| {
+            if GRADIENT_FUNCTIONS
+                .iter()
+                .any(|function| fallback.starts_with(function))
+            {
+                // The gradient is used as is, so it can't contain characters that end the
+                // declaration.
+                let is_valid = !fallback.contains([';', '{', '}']);
+                if !is_valid {
+                    warn!("Ignoring invalid gradient for 'background.fallback': '{fallback}'");
+                }
+                is_valid
+            } else {
+                is_valid_color("background.fallback", fallback)
+            }
+        });
+    }
+
+    /// Get the config from its TOML table, or the default config if the table is invalid.
+    fn from_table(table: Table) -> Self {
+        Value::Table(table).try_into().unwrap_or_else(|err| {
+            warn!("Error loading config: {err}");
+            Self::default()
+        })
+    }
+
+    /// Get the path to the stylesheet of the chosen theme bundle, if it has one.
+    pub fn get_theme_css(&self) -> Option<PathBuf> {
+        self.theme_dir
+            .as_ref()
+            .map(|dir| dir.join(THEME_CSS_FILE))
+            .filter(|path| path.exists())
     }
 
     pub fn get_env(&self) -> &HashMap<String, String> {
         &self.env
     }
 
-    pub fn get_background(&self) -> &Option<String> {
-        &self.background.path
+    /// Get the paths to background images, or to directories containing them.
+    pub fn get_background_paths(&self) -> &[String] {
+        match &self.background.path {
+            Some(BgPaths::One(path)) => std::slice::from_ref(path),
+            Some(BgPaths::Many(paths)) => paths,
+            None => &[],
+        }
+    }
+
+    pub fn get_background_order(&self) -> BgOrder {
+        self.background.order
+    }
+
+    pub fn get_background_interval(&self) -> Option<u64> {
+        self.background.interval
+    }
+
+    pub fn get_background_fallback(&self) -> Option<&str> {
+        self.background.fallback.as_deref()
+    }
+
+    /// Get the background image for the monitor with the given connector name, if it has one.
+    pub fn get_monitor_background(&self, connector: &str) -> Option<&str> {
+        self.background.monitors.get(connector).map(String::as_str)
     }
 
     #[cfg(feature = "gtk4_8")]
@@ -131,7 +854,65 @@ impl Config {
         &self.commands
     }
 
-    pub fn get_default_message(&self) -> String {
-        self.appearance.greeting_msg.clone()
+    pub fn get_user_settings(&self) -> &UserSettings {
+        &self.users
+    }
+
+    pub fn get_session_settings(&self) -> &SessionSettings {
+        &self.sessions
+    }
+
+    pub fn get_monitor_settings(&self) -> &MonitorSettings {
+        &self.monitors
+    }
+
+    pub fn get_boot_settings(&self) -> &BootSettings {
+        &self.boot
+    }
+
+    pub fn get_idle_settings(&self) -> &IdleSettings {
+        &self.idle
+    }
+
+    pub fn get_screensaver_settings(&self) -> &ScreensaverSettings {
+        &self.screensaver
+    }
+
+    pub fn get_clock_settings(&self) -> &ClockSettings {
+        &self.clock
+    }
+
+    pub fn get_issue_settings(&self) -> &IssueSettings {
+        &self.issue
+    }
+
+    pub fn get_banner_settings(&self) -> &BannerSettings {
+        &self.banner
+    }
+
+    pub fn get_appearance_settings(&self) -> &AppearanceSettings {
+        &self.appearance
+    }
+
+    pub fn get_layout_settings(&self) -> &LayoutSettings {
+        &self.layout
+    }
+
+    pub fn get_greeting_msg(&self) -> &str {
+        &self.appearance.greeting_msg
+    }
+
+    /// Get the greeting for the given hour of the day, if any are configured.
+    ///
+    /// This is the greeting with the latest start before or at this hour. Before the first start,
+    /// the greeting that started last on the previous day is used.
+    pub fn get_time_greeting(&self, hour: u32) -> Option<&str> {
+        let greetings = &self.appearance.time_greetings;
+        greetings
+            .iter()
+            .filter(|greeting| greeting.start_hour <= hour)
+            .max_by_key(|greeting| greeting.start_hour)
+            .or_else(|| greetings.iter().max_by_key(|greeting| greeting.start_hour))
+            .map(|greeting| greeting.text.as_str())
     }
 }
@@ -31,6 +31,19 @@ pub const CONFIG_PATH: &str = concatcp!(GREETD_CONFIG_DIR, "/", GREETER_NAME, ".
 /// Path to the config file
 pub const CSS_PATH: &str = concatcp!(GREETD_CONFIG_DIR, "/", GREETER_NAME, ".css");
 
+/// Directories separated by `:`, containing theme bundles, in order of preference
+pub const THEME_DIRS: &str = env_or!(
+    "THEME_DIRS",
+    concatcp!(
+        GREETD_CONFIG_DIR,
+        "/",
+        GREETER_NAME,
+        "-themes:/usr/share/",
+        GREETER_NAME,
+        "/themes"
+    )
+);
+
 /// The directory for system cache files
 const CACHE_DIR: &str = env_or!("CACHE_DIR", concatcp!("/var/cache/", GREETER_NAME));
 /// Path to the cache file
@@ -45,10 +58,43 @@ pub const LOG_PATH: &str = concatcp!(LOG_DIR, "/log");
 pub const REBOOT_CMD: &str = env_or!("REBOOT_CMD", "reboot");
 /// Default command for shutting down
 pub const POWEROFF_CMD: &str = env_or!("POWEROFF_CMD", "poweroff");
+/// Default command for suspending
+pub const SUSPEND_CMD: &str = env_or!("SUSPEND_CMD", "loginctl suspend");
+/// Default command for hibernating
+pub const HIBERNATE_CMD: &str = env_or!("HIBERNATE_CMD", "loginctl hibernate");
+
+/// Default directory containing Boot Loader Specification entries
+pub const BOOT_ENTRIES_DIR: &str = env_or!("BOOT_ENTRIES_DIR", "/boot/loader/entries");
+/// Default command for rebooting into a boot entry, where `{id}` is replaced by the entry's ID
+pub const BOOT_ENTRY_CMD: &str = env_or!(
+    "BOOT_ENTRY_CMD",
+    "systemctl reboot --boot-loader-entry={id}"
+);
+/// Default command for rebooting into the firmware setup
+pub const FIRMWARE_SETUP_CMD: &str =
+    env_or!("FIRMWARE_SETUP_CMD", "systemctl reboot --firmware-setup");
+
+/// Default time (in seconds) for which a warning is shown before the idle power action
+pub const IDLE_WARNING_SECS: u64 = 60;
+
+/// Default format for the date shown by the clock
+pub const DATE_FMT: &str = "%B %-d, %A";
+/// Default format for the time shown by the clock, in the 24-hour format
+pub const TIME_FMT_24H: &str = "%R";
+/// Default format for the time shown by the clock, in the 12-hour format
+pub const TIME_FMT_12H: &str = "%-I:%M %p";
 
 /// Default greeting message
 pub const GREETING_MSG: &str = "Welcome back!";
 
+/// Background colours for the initials shown when a user has no avatar image
+pub const AVATAR_PALETTE: [&str; 6] = [
+    "#6e7acc", "#cc6e8f", "#6eaccc", "#8fcc6e", "#cca26e", "#a06ecc",
+];
+
+/// Default label of the button that accepts the legal banner
+pub const BANNER_ACCEPT_LABEL: &str = "I agree";
+
 /// Directories separated by `:`, containing desktop files for X11/Wayland sessions
 pub const SESSION_DIRS: &str = env_or!(
     "SESSION_DIRS",
@@ -4,26 +4,58 @@
 
 //! Setup for using the greeter as a Relm4 component
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use chrono::Local;
-use tracing::{debug, warn};
+use chrono::{Local, Timelike};
+use tracing::{debug, info, warn};
 
 use gtk::prelude::*;
 use relm4::{
     component::{AsyncComponent, AsyncComponentParts, AsyncComponentSender},
-    gtk,
+    gtk, WidgetTemplate,
 };
 use tokio::time::sleep;
 
 use super::messages::{CommandMsg, InputMsg};
-use super::model::{Greeter, Updates};
-use super::templates::Ui;
+use super::model::{Greeter, InputMode, Screensaver, Updates};
+use super::templates::{EndButton, Ui, SESSION_ICON_SIZE};
+use crate::config::{AppearanceSettings, LayoutAlign, LayoutPreset, LayoutSettings};
+use crate::constants::AVATAR_PALETTE;
+use crate::sysutil::{SessionInfo, SysUtil};
 
-const DATE_FMT: &str = "<b>%B %-d, %A</b>";
-const TIME_FMT: &str = "<b><big>%R</big></b>";
-const DATETIME_UPDATE_DELAY: u64 = 500;
+/// Interval (in seconds) between checks for new/removed users and sessions
+const SYS_WATCH_DELAY: u64 = 5;
+/// Interval (in seconds) between checks for whether the greeter is idle
+const IDLE_CHECK_DELAY: u64 = 1;
+/// Icon used for sessions without a valid icon
+const FALLBACK_SESSION_ICON: &str = "video-display";
+/// CSS class of windows when the screen is dimmed
+const DIMMED_CLASS: &str = "dimmed";
+/// CSS class of windows when the screen is blanked
+const BLANK_CLASS: &str = "blank";
+/// Built-in default stylesheet, which the custom CSS can override
+const DEFAULT_CSS: &str = include_str!("style.css");
+/// Priority of the built-in CSS, which is below that of the theme CSS
+const BUILTIN_CSS_PRIORITY: u32 = gtk::STYLE_PROVIDER_PRIORITY_APPLICATION - 3;
+/// Priority of the stylesheet of the theme bundle, which is below that of the palette CSS
+const THEME_CSS_PRIORITY: u32 = gtk::STYLE_PROVIDER_PRIORITY_APPLICATION - 2;
+/// Priority of the CSS generated from the appearance settings, which is below that of the custom
+/// CSS
+const PALETTE_CSS_PRIORITY: u32 = gtk::STYLE_PROVIDER_PRIORITY_APPLICATION - 1;
+/// Maximum width (in characters) of the text in the legal banner
+const BANNER_WIDTH_CHARS: i32 = 80;
+/// Maximum height (in pixels) of the text in the legal banner, beyond which it's scrolled
+const BANNER_MAX_HEIGHT: i32 = 400;
+/// CSS selectors of the clock, the login panel and the box with the power action buttons, in the
+/// order of the layout settings
+const LAYOUT_BLOCK_SELECTORS: [&str; 3] = ["#clock", "#login-panel", "#bottom-box"];
+/// Directory that only exists on EFI systems
+const EFI_FIRMWARE_DIR: &str = "/sys/firmware/efi";
+/// CSS class of the window with the login UI
+const MAIN_WINDOW_CLASS: &str = "main-window";
+/// CSS class of the windows that only show the background on other monitors
+const BACKGROUND_WINDOW_CLASS: &str = "background-window";
 
 /// Load GTK settings from the greeter config.
 fn setup_settings(model: &Greeter, root: &gtk::ApplicationWindow) {
@@ -61,19 +93,735 @@ fn setup_settings(model: &Greeter, root: &gtk::ApplicationWindow) {
     };
 }
 
-/// Set up auto updation for the datetime label.
-fn setup_datetime_display(sender: &AsyncComponentSender<Greeter>) {
+/// Show a session's icon, as given in its desktop file.
+///
+/// The icon is either an absolute path, or a name that is looked up in the configured icon theme.
+fn set_session_icon(image: &gtk::Image, icon: Option<&str>) {
+    match icon {
+        Some(icon) if Path::new(icon).is_absolute() => image.set_from_file(Some(icon)),
+        Some(icon) if gtk::IconTheme::for_display(&image.display()).has_icon(icon) => {
+            image.set_icon_name(Some(icon))
+        }
+        _ => {
+            if let Some(icon) = icon {
+                debug!("Icon '{icon}' not found in icon theme; using fallback");
+            }
+            image.set_icon_name(Some(FALLBACK_SESSION_ICON))
+        }
+    }
+}
+
+/// Populate the users dropdown, and select the current user in it.
+fn setup_users(model: &Greeter, usernames_box: &gtk::ComboBoxText) {
+    usernames_box.remove_all();
+    for (username, full_name) in &model.updates.users {
+        debug!("Found user: {full_name}");
+        usernames_box.append(Some(username.as_str()), full_name);
+    }
+    select_user(model, usernames_box);
+}
+
+/// Select the current user in the users dropdown.
+fn select_user(model: &Greeter, usernames_box: &gtk::ComboBoxText) {
+    let user_id = model.updates.active_user_id.as_deref();
+    if !usernames_box.set_active_id(user_id) {
+        if let Some(user) = user_id {
+            warn!("Couldn't find user '{user}' to select");
+        }
+    }
+}
+
+/// Create a row for the sessions list, showing the session's icon, name and description.
+fn create_session_row(name: &str, info: &SessionInfo) -> gtk::ListBoxRow {
+    let icon = gtk::Image::new();
+    icon.set_pixel_size(SESSION_ICON_SIZE);
+    set_session_icon(&icon, info.icon.as_deref());
+
+    let text_box = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    text_box.set_valign(gtk::Align::Center);
+    let name_label = gtk::Label::new(Some(name));
+    name_label.set_xalign(0.0);
+    text_box.append(&name_label);
+    if let Some(comment) = &info.comment {
+        let comment_label = gtk::Label::new(Some(comment.as_str()));
+        comment_label.set_xalign(0.0);
+        comment_label.add_css_class("dim-label");
+        text_box.append(&comment_label);
+    }
+
+    let row_box = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+    row_box.append(&icon);
+    row_box.append(&text_box);
+
+    let row = gtk::ListBoxRow::new();
+    row.set_child(Some(&row_box));
+    // Name the row after the session, so that the chosen session can be identified.
+    row.set_widget_name(name);
+    row
+}
+
+/// Populate the sessions list.
+fn setup_sessions(model: &Greeter, sessions_list: &gtk::ListBox) {
+    while let Some(row) = sessions_list.first_child() {
+        sessions_list.remove(&row);
+    }
+    for name in &model.updates.sessions {
+        if let Some(info) = model.sys_util.get_sessions().get(name) {
+            debug!("Found session: {name}");
+            sessions_list.append(&create_session_row(name, info));
+        }
+    }
+}
+
+/// Ask the user whether to run a power action, and run it if they agree.
+fn confirm_power_action(
+    widget: &impl IsA<gtk::Widget>,
+    label: &str,
+    message: impl Fn() -> InputMsg + 'static,
+    sender: &AsyncComponentSender<Greeter>,
+) {
+    let dialog = gtk::MessageDialog::builder()
+        .modal(true)
+        .message_type(gtk::MessageType::Question)
+        .text(&format!("{label}?"))
+        .build();
+    if let Some(window) = widget
+        .root()
+        .and_then(|root| root.downcast::<gtk::Window>().ok())
+    {
+        dialog.set_transient_for(Some(&window));
+    }
+    dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    dialog.add_button(label, gtk::ResponseType::Accept);
+    dialog.set_default_response(gtk::ResponseType::Cancel);
+
+    let sender = sender.clone();
+    dialog.connect_response(move |dialog, response| {
+        if response == gtk::ResponseType::Accept {
+            sender.input(message());
+        }
+        dialog.destroy();
+    });
+    dialog.present();
+}
+
+/// Show the legal banner (if any), which must be accepted before logging in.
+fn setup_banner(
+    model: &Greeter,
+    root: &gtk::ApplicationWindow,
+    sender: &AsyncComponentSender<Greeter>,
+) {
+    let text = if let Some(text) = &model.updates.banner {
+        text
+    } else {
+        return;
+    };
+    let dialog = gtk::MessageDialog::builder()
+        .modal(true)
+        .deletable(false)
+        .transient_for(root)
+        .message_type(gtk::MessageType::Other)
+        .build();
+    dialog.set_widget_name("banner-dialog");
+
+    // Scroll long notices, instead of making the dialog taller than the screen.
+    let label = gtk::Label::builder()
+        .label(text)
+        .max_width_chars(BANNER_WIDTH_CHARS)
+        .wrap(true)
+        .xalign(0.0)
+        .build();
+    let scroller = gtk::ScrolledWindow::builder()
+        .child(&label)
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .max_content_height(BANNER_MAX_HEIGHT)
+        .propagate_natural_height(true)
+        .build();
+    dialog.content_area().append(&scroller);
+
+    dialog.add_button(
+        &model.config.get_banner_settings().accept_label,
+        gtk::ResponseType::Accept,
+    );
+    dialog.set_default_response(gtk::ResponseType::Accept);
+    // Don't let the banner be dismissed (eg. with Escape) without accepting it.
+    dialog.connect_close_request(|_| gtk::Inhibit(true));
+
+    let accept_sender = sender.clone();
+    dialog.connect_response(move |dialog, response| {
+        if response == gtk::ResponseType::Accept {
+            accept_sender.input(InputMsg::AcceptBanner);
+            dialog.destroy();
+        }
+    });
+    // The banner is a separate window, so input on it must also keep the greeter awake.
+    watch_activity(&dialog, sender);
+    dialog.present();
+}
+
+/// Add a button for every power action (eg. Reboot) in the config.
+fn setup_power_actions(
+    model: &Greeter,
+    power_actions_box: &gtk::Box,
+    sender: &AsyncComponentSender<Greeter>,
+) {
+    for (index, action) in model
+        .config
+        .get_sys_commands()
+        .get_actions()
+        .into_iter()
+        .enumerate()
+    {
+        let button = EndButton::init();
+        if let Err(reason) = SysUtil::check_command(&action.command) {
+            // Keep the button visible, so that the reason is discoverable.
+            warn!("Disabling power action '{}': {reason}", action.label);
+            button.set_sensitive(false);
+            button.set_tooltip_text(Some(&format!("Unavailable: {reason}")));
+        } else {
+            button.set_tooltip_text(Some(&action.label));
+        }
+        if let Some(icon) = &action.icon {
+            let content = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+            content.set_halign(gtk::Align::Center);
+            content.append(&gtk::Image::from_icon_name(icon));
+            content.append(&gtk::Label::new(Some(&action.label)));
+            button.set_child(Some(&content));
+        } else {
+            button.set_label(&action.label);
+        }
+
+        let sender = sender.clone();
+        button.connect_clicked(move |button| {
+            if action.confirm {
+                confirm_power_action(
+                    button,
+                    &action.label,
+                    move || InputMsg::PowerAction(index),
+                    &sender,
+                );
+            } else {
+                sender.input(InputMsg::PowerAction(index));
+            }
+        });
+        power_actions_box.append(&*button);
+    }
+}
+
+/// Add a button with a menu to reboot into a boot entry or the firmware setup, if any are
+/// available.
+fn setup_reboot_menu(
+    model: &Greeter,
+    power_actions_box: &gtk::Box,
+    sender: &AsyncComponentSender<Greeter>,
+) {
+    let settings = model.config.get_boot_settings();
+    // Titles of the menu entries, with the IDs of the boot entries (`None` for firmware setup)
+    let mut targets = Vec::new();
+    match SysUtil::check_command(&settings.entry_command) {
+        Ok(()) => targets.extend(
+            SysUtil::get_boot_entries(Path::new(&settings.entries_dir))
+                .into_iter()
+                .map(|entry| (entry.title, Some(entry.id))),
+        ),
+        Err(reason) => warn!("Not showing boot entries: {reason}"),
+    }
+    // Only EFI systems have a firmware setup that can be rebooted into.
+    if Path::new(EFI_FIRMWARE_DIR).exists() {
+        match SysUtil::check_command(&settings.firmware_command) {
+            Ok(()) => targets.push(("Firmware Setup".to_string(), None)),
+            Err(reason) => warn!("Not showing firmware setup: {reason}"),
+        }
+    }
+    if targets.is_empty() {
+        return;
+    }
+
+    let targets_list = gtk::ListBox::new();
+    targets_list.set_widget_name("reboot-menu");
+    targets_list.set_selection_mode(gtk::SelectionMode::None);
+    for (title, _) in &targets {
+        let label = gtk::Label::new(Some(title));
+        label.set_xalign(0.0);
+        targets_list.append(&label);
+    }
+    let popover = gtk::Popover::new();
+    popover.set_child(Some(&targets_list));
+
+    let button = EndButton::init();
+    button.set_widget_name("reboot-menu-button");
+    button.set_label("Reboot Into…");
+    popover.set_parent(&*button);
+    let menu = popover.clone();
+    button.connect_clicked(move |_| menu.popup());
+
+    let sender = sender.clone();
+    targets_list.connect_row_activated(move |_, row| {
+        popover.popdown();
+        if let Some((title, entry_id)) = usize::try_from(row.index())
+            .ok()
+            .and_then(|index| targets.get(index))
+        {
+            let entry_id = entry_id.clone();
+            confirm_power_action(
+                row,
+                &format!("Reboot into {title}"),
+                move || InputMsg::RebootInto(entry_id.clone()),
+                &sender,
+            );
+        }
+    });
+    power_actions_box.append(&*button);
+}
+
+/// Get the CSS rule that applies the declarations to the selectors.
+fn css_rule(selectors: &str, declarations: &str) -> String {
+    format!("{selectors} {{ {declarations} }}\n")
+}
+
+/// Generate CSS from the colours, sizes and fonts in the appearance settings.
+///
+/// Invalid settings should've already been removed when loading the config.
+fn get_palette_css(settings: &AppearanceSettings) -> String {
+    let mut css = String::new();
+
+    if let Some(color) = &settings.accent_color {
+        css.push_str(&css_rule("#clock", &format!("color: {color};")));
+        css.push_str(&css_rule(
+            ".end-button, #error-label",
+            &format!("background-color: {color};"),
+        ));
+        css.push_str(&css_rule(
+            ".end-button, .input-entry",
+            &format!("outline-color: {color};"),
+        ));
+    }
+    if let Some(color) = &settings.foreground_color {
+        css.push_str(&css_rule(
+            "#message-label, #input-prompt-label, #avatar-initials, #error-label, #issue-label, \
+             .end-button",
+            &format!("color: {color};"),
+        ));
+        css.push_str(&css_rule(
+            "#session-comment",
+            &format!("color: alpha({color}, 0.7);"),
+        ));
+    }
+    if let Some(color) = &settings.entry_background {
+        css.push_str(&css_rule(
+            ".input-entry",
+            &format!("background-color: {color};"),
+        ));
+    }
+
+    if let Some(radius) = settings.corner_radius {
+        css.push_str(&css_rule(
+            ".input-entry, .end-button, #error-label, #login-panel, #issue-panel",
+            &format!("border-radius: {radius}px;"),
+        ));
+    }
+    if let Some(opacity) = settings.panel_opacity {
+        css.push_str(&css_rule(
+            "#login-panel, #issue-panel",
+            &format!("background-color: rgba(0, 0, 0, {opacity});"),
+        ));
+    }
+
+    if let Some(font) = &settings.clock_font {
+        css.push_str(&css_rule("#clock", &format!("font-family: \"{font}\";")));
+    }
+    if let Some(size) = settings.clock_font_size {
+        css.push_str(&css_rule("#time-label", &format!("font-size: {size}px;")));
+    }
+
+    css
+}
+
+/// Get the CSS for the colour or gradient shown where there's no background image, if any.
+fn get_background_css(fallback: Option<&str>) -> String {
+    let fallback = if let Some(fallback) = fallback {
+        fallback
+    } else {
+        return String::new();
+    };
+    // The blank screensaver must still hide the background.
+    let selectors = [MAIN_WINDOW_CLASS, BACKGROUND_WINDOW_CLASS]
+        .map(|class| format!("window.{class}:not(.{BLANK_CLASS})"))
+        .join(", ");
+    css_rule(&selectors, &format!("background: {fallback};"))
+}
+
+/// Get the CSS for the margins of the blocks of the UI (eg. the clock) in the layout settings.
+///
+/// Margins that aren't set are left to the stylesheets, which also have the margins of the
+/// presets.
+fn get_layout_css(settings: &LayoutSettings) -> String {
+    let mut css = String::new();
+    for (selector, layout) in LAYOUT_BLOCK_SELECTORS.iter().zip(settings.get_layouts()) {
+        let margins = [
+            ("top", layout.margin_top),
+            ("bottom", layout.margin_bottom),
+            ("left", layout.margin_left),
+            ("right", layout.margin_right),
+        ];
+        let declarations = margins
+            .iter()
+            .filter_map(|(side, margin)| margin.map(|margin| format!("margin-{side}: {margin}px;")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !declarations.is_empty() {
+            css.push_str(&css_rule(selector, &declarations));
+        }
+    }
+    css
+}
+
+/// Load the built-in CSS, which has:
+/// - the default stylesheet
+/// - the colours used behind a user's initials, as CSS classes `avatar-color-<index>`
+/// - the CSS generated from the appearance, layout and background settings, which overrides the
+///   default stylesheet
+fn setup_builtin_css(model: &Greeter, root: &gtk::ApplicationWindow) {
+    let mut css = DEFAULT_CSS.to_string();
+    for (i, color) in AVATAR_PALETTE.iter().enumerate() {
+        css.push_str(&format!(
+            ".avatar-color-{i} {{ background-color: {color}; }}\n"
+        ));
+    }
+    let provider = gtk::CssProvider::new();
+    provider.load_from_data(css.as_bytes());
+    gtk::StyleContext::add_provider_for_display(
+        &WidgetExt::display(root),
+        &provider,
+        BUILTIN_CSS_PRIORITY,
+    );
+
+    let palette_css = get_palette_css(model.config.get_appearance_settings())
+        + &get_layout_css(model.config.get_layout_settings())
+        + &get_background_css(model.config.get_background_fallback());
+    if !palette_css.is_empty() {
+        debug!("Generated CSS from appearance, layout and background settings:\n{palette_css}");
+        let provider = gtk::CssProvider::new();
+        provider.load_from_data(palette_css.as_bytes());
+        gtk::StyleContext::add_provider_for_display(
+            &WidgetExt::display(root),
+            &provider,
+            PALETTE_CSS_PRIORITY,
+        );
+    }
+}
+
+/// Convert the alignment in the layout settings to the GTK alignment.
+fn get_align(align: LayoutAlign) -> gtk::Align {
+    match align {
+        LayoutAlign::Start => gtk::Align::Start,
+        LayoutAlign::Center => gtk::Align::Center,
+        LayoutAlign::End => gtk::Align::End,
+        LayoutAlign::Fill => gtk::Align::Fill,
+    }
+}
+
+/// Position or hide the blocks of the UI (eg. the clock), according to the layout settings.
+///
+/// The margins of the preset are in the default stylesheet, and the margins set explicitly are in
+/// the CSS generated from the layout settings.
+fn setup_layout(model: &Greeter, root: &gtk::ApplicationWindow, ui: &Ui) {
+    let settings = model.config.get_layout_settings();
+    // Let stylesheets style each preset differently.
+    root.add_css_class(match settings.preset {
+        LayoutPreset::Default => "layout-default",
+        LayoutPreset::CenteredCard => "layout-centered-card",
+        LayoutPreset::LeftSidebar => "layout-left-sidebar",
+        LayoutPreset::Minimal => "layout-minimal",
+    });
+
+    let blocks: [&gtk::Widget; 3] = [
+        ui.clock_box.upcast_ref(),
+        ui.login_panel.upcast_ref(),
+        ui.bottom_box.upcast_ref(),
+    ];
+    for (block, layout) in blocks.into_iter().zip(settings.get_layouts()) {
+        if let Some(halign) = layout.halign {
+            block.set_halign(get_align(halign));
+        }
+        if let Some(valign) = layout.valign {
+            block.set_valign(get_align(valign));
+        }
+        if layout.hidden == Some(true) {
+            block.set_visible(false);
+        }
+    }
+}
+
+/// Load the stylesheet of the theme bundle in the config, if any.
+fn setup_theme_css(model: &Greeter, root: &gtk::ApplicationWindow) {
+    if let Some(css_path) = model.config.get_theme_css() {
+        debug!("Loading theme CSS from file: {}", css_path.display());
+        let provider = gtk::CssProvider::new();
+        provider.load_from_path(css_path);
+        gtk::StyleContext::add_provider_for_display(
+            &WidgetExt::display(root),
+            &provider,
+            THEME_CSS_PRIORITY,
+        );
+    }
+}
+
+/// Dim or blank all windows of the greeter, according to the screensaver state.
+fn set_screensaver_css(root: &gtk::ApplicationWindow, screensaver: Screensaver) {
+    for window in root.application().iter().flat_map(|app| app.windows()) {
+        match screensaver {
+            Screensaver::Off => {
+                window.remove_css_class(DIMMED_CLASS);
+                window.remove_css_class(BLANK_CLASS);
+            }
+            Screensaver::Dimmed => {
+                window.add_css_class(DIMMED_CLASS);
+                window.remove_css_class(BLANK_CLASS);
+            }
+            Screensaver::Blank => {
+                window.remove_css_class(DIMMED_CLASS);
+                window.add_css_class(BLANK_CLASS);
+            }
+        }
+    }
+}
+
+/// Show the background images on the window with the login UI, and on the background windows.
+fn set_backgrounds(model: &Greeter, root: &gtk::ApplicationWindow, background: &gtk::Picture) {
+    let connector = model
+        .updates
+        .monitor
+        .as_ref()
+        .and_then(|monitor| monitor.connector());
+    background.set_filename(model.get_background(connector.as_deref()));
+
+    for window in root.application().iter().flat_map(|app| app.windows()) {
+        if !window.has_css_class(BACKGROUND_WINDOW_CLASS) {
+            continue;
+        }
+        if let Some(picture) = window
+            .child()
+            .and_then(|child| child.downcast::<gtk::Picture>().ok())
+        {
+            picture.set_filename(model.get_background(Some(window.widget_name().as_str())));
+        }
+    }
+}
+
+/// Get how the background image should cover the screen.
+#[cfg(feature = "gtk4_8")]
+fn get_content_fit(model: &Greeter) -> gtk4::ContentFit {
+    use crate::config::BgFit;
+    match model.config.get_background_fit() {
+        BgFit::Fill => gtk4::ContentFit::Fill,
+        BgFit::Contain => gtk4::ContentFit::Contain,
+        BgFit::Cover => gtk4::ContentFit::Cover,
+        BgFit::ScaleDown => gtk4::ContentFit::ScaleDown,
+    }
+}
+
+/// Show the background on every monitor other than the one with the login UI.
+///
+/// This replaces the background windows created earlier, since the monitors may have changed.
+fn setup_background_windows(
+    model: &Greeter,
+    root: &gtk::ApplicationWindow,
+    sender: &AsyncComponentSender<Greeter>,
+) {
+    let app = root.application();
+    for window in app.iter().flat_map(|app| app.windows()) {
+        if window.has_css_class(BACKGROUND_WINDOW_CLASS) {
+            window.destroy();
+        }
+    }
+
+    for monitor in &model.updates.other_monitors {
+        debug!("Showing background on monitor: {monitor}");
+        let connector = monitor.connector();
+        let background = gtk::Picture::new();
+        background.add_css_class("background");
+        background.set_filename(model.get_background(connector.as_deref()));
+        #[cfg(feature = "gtk4_8")]
+        background.set_content_fit(get_content_fit(model));
+
+        let window = gtk::ApplicationWindow::builder().child(&background).build();
+        window.set_application(app.as_ref());
+        window.add_css_class(BACKGROUND_WINDOW_CLASS);
+        // Name the window after the monitor, so that its background can be changed later.
+        if let Some(connector) = &connector {
+            window.set_widget_name(connector);
+        }
+        watch_activity(&window, sender);
+        if model.config.get_monitor_settings().follow_pointer {
+            if let Some(connector) = monitor.connector() {
+                // Move the login UI here when the pointer moves to this monitor.
+                let motion = gtk::EventControllerMotion::new();
+                let motion_sender = sender.clone();
+                let motion_connector = connector.clone();
+                motion.connect_enter(move |_, _, _| {
+                    motion_sender.input(InputMsg::MonitorActivated(motion_connector.clone()))
+                });
+                window.add_controller(&motion);
+
+                // Also move it here when this monitor is clicked or touched, which gives its window
+                // the keyboard focus.
+                let click = gtk::GestureClick::new();
+                let click_sender = sender.clone();
+                let click_connector = connector.clone();
+                click.connect_pressed(move |_, _, _, _| {
+                    click_sender.input(InputMsg::MonitorActivated(click_connector.clone()))
+                });
+                window.add_controller(&click);
+
+                // Also move it here when a key is pressed on this monitor, eg. after the compositor
+                // moved the keyboard focus here.
+                let keys = gtk::EventControllerKey::new();
+                let keys_sender = sender.clone();
+                keys.connect_key_pressed(move |_, _, _, _| {
+                    keys_sender.input(InputMsg::MonitorActivated(connector.clone()));
+                    gtk::Inhibit(false)
+                });
+                window.add_controller(&keys);
+            }
+        }
+        window.present();
+        window.fullscreen_on_monitor(monitor);
+    }
+
+    // Keep the keyboard focus on the login UI.
+    root.present();
+}
+
+/// Set up auto updation for the datetime label, and for the time-of-day in the greeting.
+///
+/// Without the clock, the greeting still needs to be updated every minute.
+fn setup_datetime_display(model: &Greeter, sender: &AsyncComponentSender<Greeter>) {
+    let has_seconds = model.config.get_clock_settings().enabled && model.clock.has_seconds;
+
     // Set a timer in a separate thread that signals the main thread to update the time, so as to
     // not block the GUI.
-    sender.command(|sender, shutdown| {
+    sender.command(move |sender, shutdown| {
         shutdown
             .register(async move {
-                // Run it infinitely, since the clock always needs to stay updated.
+                // Run it infinitely, since the clock and the greeting always need to stay updated.
                 loop {
                     if sender.send(CommandMsg::UpdateTime).is_err() {
                         warn!("Couldn't update datetime");
                     };
-                    sleep(Duration::from_millis(DATETIME_UPDATE_DELAY)).await;
+                    // Wake up at the start of the next second or minute, when the clock changes.
+                    let now = Local::now();
+                    let mut delay = Duration::from_nanos(
+                        1_000_000_000 - u64::from(now.nanosecond() % 1_000_000_000),
+                    );
+                    if !has_seconds {
+                        delay += Duration::from_secs(59 - u64::from(now.second().min(59)));
+                    }
+                    sleep(delay).await;
+                }
+            })
+            .drop_on_shutdown()
+    });
+}
+
+/// Set up periodic checks for whether the greeter has been idle for too long, if the idle power
+/// action or the screensaver is enabled.
+fn setup_idle_watcher(model: &Greeter, sender: &AsyncComponentSender<Greeter>) {
+    let screensaver_settings = model.config.get_screensaver_settings();
+    if model.config.get_idle_settings().timeout.is_none()
+        && screensaver_settings.dim_timeout.is_none()
+        && screensaver_settings.blank_timeout.is_none()
+    {
+        return;
+    }
+    sender.command(|sender, shutdown| {
+        shutdown
+            .register(async move {
+                loop {
+                    sleep(Duration::from_secs(IDLE_CHECK_DELAY)).await;
+                    if sender.send(CommandMsg::IdleTick).is_err() {
+                        warn!("Couldn't check for idleness");
+                    };
+                }
+            })
+            .drop_on_shutdown()
+    });
+}
+
+/// Set up changing the background image after every interval in the config.
+fn setup_background_rotation(model: &Greeter, sender: &AsyncComponentSender<Greeter>) {
+    let interval = if let Some(interval) = model.config.get_background_interval() {
+        interval
+    } else {
+        return;
+    };
+    if model.backgrounds.len() < 2 || interval == 0 {
+        return;
+    }
+    sender.command(move |sender, shutdown| {
+        shutdown
+            .register(async move {
+                loop {
+                    sleep(Duration::from_secs(interval)).await;
+                    if sender.send(CommandMsg::RotateBackground).is_err() {
+                        warn!("Couldn't change the background image");
+                    };
+                }
+            })
+            .drop_on_shutdown()
+    });
+}
+
+/// Notify the greeter of any input in the window, before its widgets handle it.
+fn watch_activity(window: &impl IsA<gtk::Widget>, sender: &AsyncComponentSender<Greeter>) {
+    let keys = gtk::EventControllerKey::new();
+    keys.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let keys_sender = sender.clone();
+    keys.connect_key_pressed(move |_, _, _, _| {
+        keys_sender.input(InputMsg::Activity);
+        gtk::Inhibit(false)
+    });
+    window.add_controller(&keys);
+
+    let motion = gtk::EventControllerMotion::new();
+    motion.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let motion_sender = sender.clone();
+    motion.connect_motion(move |_, _, _| motion_sender.input(InputMsg::Activity));
+    window.add_controller(&motion);
+
+    let click = gtk::GestureClick::new();
+    click.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let click_sender = sender.clone();
+    click.connect_pressed(move |_, _, _, _| click_sender.input(InputMsg::Activity));
+    window.add_controller(&click);
+}
+
+/// Set up polling for changes to the available users and sessions.
+fn setup_sys_watcher(model: &Greeter, sender: &AsyncComponentSender<Greeter>) {
+    let user_settings = model.config.get_user_settings().clone();
+    let sess_settings = model.config.get_session_settings().clone();
+    // Poll in a separate thread, so as to not block the GUI.
+    sender.command(|sender, shutdown| {
+        shutdown
+            .register(async move {
+                let mut last_mtimes = SysUtil::get_source_mtimes(&sess_settings);
+                loop {
+                    sleep(Duration::from_secs(SYS_WATCH_DELAY)).await;
+                    let mtimes = SysUtil::get_source_mtimes(&sess_settings);
+                    if mtimes == last_mtimes {
+                        continue;
+                    }
+                    last_mtimes = mtimes;
+
+                    info!("Users or sessions changed on the system; reloading them");
+                    match SysUtil::new(&user_settings, &sess_settings) {
+                        Ok(sys_util) => {
+                            if sender.send(CommandMsg::UpdateSysUtil(sys_util)).is_err() {
+                                warn!("Couldn't update users and sessions");
+                            };
+                        }
+                        Err(err) => warn!("Couldn't reload users and sessions: {err}"),
+                    };
                 }
             })
             .drop_on_shutdown()
@@ -99,26 +847,223 @@ impl AsyncComponent for Greeter {
         #[name = "window"]
         gtk::ApplicationWindow {
             set_visible: true,
+            add_css_class: MAIN_WINDOW_CLASS,
+
+            // Cancel the login attempt when Escape is pressed anywhere.
+            add_controller = &gtk::EventControllerKey {
+                connect_key_pressed[sender] => move |_, key, _, _| {
+                    if key == gtk::gdk::Key::Escape {
+                        sender.input(Self::Input::Cancel);
+                        gtk::Inhibit(true)
+                    } else {
+                        gtk::Inhibit(false)
+                    }
+                },
+            },
 
             // Name the UI widget, otherwise the inner children cannot be accessed by name.
             #[name = "ui"]
             #[template]
             Ui {
-                #[template_child]
-                background { set_filename: model.config.get_background().clone() },
                 #[template_child]
                 date_label {
                     #[track(model.updates.changed(Updates::date()))]
-                    set_label: &model.updates.date
+                    set_label: &model.updates.date,
+                    #[track(model.updates.changed(Updates::screensaver()))]
+                    set_visible: model.shows_clock(),
                 },
                 #[template_child]
                 time_label {
                     #[track(model.updates.changed(Updates::time()))]
-                    set_label: &model.updates.time
+                    set_label: &model.updates.time,
+                    #[track(model.updates.changed(Updates::screensaver()))]
+                    set_visible: model.shows_clock(),
+                },
+                #[template_child]
+                timezones_label {
+                    #[track(model.updates.changed(Updates::timezones()))]
+                    set_label: &model.updates.timezones,
+                    #[track(model.updates.changed(Updates::timezones() | Updates::screensaver()))]
+                    set_visible: model.shows_clock() && !model.updates.timezones.is_empty(),
+                },
+                #[template_child]
+                issue_panel {
+                    #[track(model.updates.changed(Updates::issue()))]
+                    set_visible: !model.updates.issue.is_empty(),
+                },
+                #[template_child]
+                issue_label {
+                    #[track(model.updates.changed(Updates::issue()))]
+                    set_label: &model.updates.issue,
+                },
+                #[template_child]
+                login_box {
+                    #[track(model.updates.changed(Updates::banner()))]
+                    set_sensitive: model.updates.banner.is_none(),
+                    #[track(model.updates.changed(Updates::screensaver()))]
+                    set_visible: model.updates.screensaver != Screensaver::Blank,
+                },
+                #[template_child]
+                power_actions_box {
+                    #[track(model.updates.changed(Updates::screensaver()))]
+                    set_visible: model.updates.screensaver != Screensaver::Blank,
+                },
+                #[template_child]
+                busy_spinner {
+                    #[track(model.updates.changed(Updates::busy()))]
+                    set_visible: model.updates.busy,
+                    #[track(model.updates.changed(Updates::busy()))]
+                    set_spinning: model.updates.busy,
+                },
+                #[template_child]
+                idle_warning_label {
+                    #[track(model.updates.changed(Updates::idle_warning()))]
+                    set_visible: model.updates.idle_warning.is_some(),
+                    #[track(model.updates.changed(Updates::idle_warning()))]
+                    set_label: model.updates.idle_warning.as_deref().unwrap_or_default(),
+                },
+                #[template_child]
+                avatar_image {
+                    #[track(model.updates.changed(Updates::avatar()))]
+                    set_visible: model.updates.avatar.is_some(),
+                    #[track(model.updates.changed(Updates::avatar()))]
+                    set_from_file: model.updates.avatar.as_ref(),
+                },
+                #[template_child]
+                avatar_initials {
+                    #[track(model.updates.changed(Updates::avatar()))]
+                    set_visible: model.updates.avatar.is_none(),
+                    #[track(model.updates.changed(Updates::initials()))]
+                    set_label: &model.updates.initials,
+                    #[track(model.updates.changed(Updates::avatar_color()))]
+                    set_css_classes: &[format!("avatar-color-{}", model.updates.avatar_color).as_str()],
+                },
+                // The rest of the login box stays sensitive while busy, so that the login attempt
+                // can be cancelled.
+                #[template_child]
+                user_chooser {
+                    #[track(model.updates.changed(Updates::busy()))]
+                    set_sensitive: !model.updates.busy,
+                },
+                #[template_child]
+                session_details {
+                    #[track(model.updates.changed(Updates::busy()))]
+                    set_sensitive: !model.updates.busy,
+                },
+                #[template_child]
+                usernames_box {
+                    #[track(model.updates.changed(Updates::manual_user_mode()))]
+                    set_visible: !model.updates.manual_user_mode,
+                    connect_changed[sender, username_entry] => move |this| {
+                        sender.input(Self::Input::UserChanged {
+                            user_id: this.active_id(),
+                            user_text: username_entry.text(),
+                        })
+                    },
+                },
+                #[template_child]
+                username_entry {
+                    #[track(model.updates.changed(Updates::manual_user_mode()))]
+                    set_visible: model.updates.manual_user_mode,
+                    connect_changed[sender, usernames_box] => move |this| {
+                        sender.input(Self::Input::UserChanged {
+                            user_id: usernames_box.active_id(),
+                            user_text: this.text(),
+                        })
+                    },
+                },
+                #[template_child]
+                user_toggle {
+                    #[track(model.updates.changed(Updates::manual_user_mode()))]
+                    set_active: model.updates.manual_user_mode,
+                    #[track(model.updates.changed(Updates::users()))]
+                    set_sensitive: !model.updates.users.is_empty(),
+                    connect_clicked => Self::Input::ToggleManualUser,
+                },
+                #[template_child]
+                session_icon {
+                    #[track(model.updates.changed(Updates::manual_sess_mode()))]
+                    set_visible: !model.updates.manual_sess_mode,
+                },
+                #[template_child]
+                sessions_button {
+                    #[track(model.updates.changed(Updates::manual_sess_mode()))]
+                    set_visible: !model.updates.manual_sess_mode,
+                    #[track(model.updates.changed(Updates::sess_name()))]
+                    set_label: &model.updates.sess_name,
+                },
+                #[template_child]
+                sessions_list {
+                    connect_row_activated[sender, sessions_button] => move |_, row| {
+                        sessions_button.popdown();
+                        // The row's name is the session name.
+                        sender.input(Self::Input::SessionChanged(row.widget_name()))
+                    },
+                },
+                #[template_child]
+                session_entry {
+                    #[track(model.updates.changed(Updates::manual_sess_mode()))]
+                    set_visible: model.updates.manual_sess_mode,
+                    connect_changed[sender] => move |this| {
+                        sender.input(Self::Input::SessionCmdChanged(this.text()))
+                    },
+                },
+                #[template_child]
+                sess_toggle {
+                    #[track(model.updates.changed(Updates::manual_sess_mode()))]
+                    set_active: model.updates.manual_sess_mode,
+                    #[track(model.updates.changed(Updates::sessions()))]
+                    set_sensitive: !model.updates.sessions.is_empty(),
+                    connect_clicked => Self::Input::ToggleManualSess,
+                },
+                #[template_child]
+                session_comment {
+                    #[track(model.updates.changed(
+                        Updates::manual_sess_mode() | Updates::sess_comment()
+                    ))]
+                    set_visible: !model.updates.manual_sess_mode
+                        && model.updates.sess_comment.is_some(),
+                    #[track(model.updates.changed(Updates::sess_comment()))]
+                    set_label: model.updates.sess_comment.as_deref().unwrap_or_default(),
+                },
+                #[template_child]
+                message_label {
+                    #[track(model.updates.changed(Updates::message()))]
+                    set_label: &model.updates.message,
+                },
+                #[template_child]
+                input_prompt_label {
+                    #[track(model.updates.changed(
+                        Updates::input_mode() | Updates::input_prompt()
+                    ))]
+                    set_visible: model.updates.input_mode != InputMode::None
+                        && !model.updates.input_prompt.is_empty(),
+                    #[track(model.updates.changed(Updates::input_prompt()))]
+                    set_label: &model.updates.input_prompt,
                 },
                 #[template_child]
                 secret_entry {
                     grab_focus: (),
+                    #[track(model.updates.changed(Updates::busy()))]
+                    set_sensitive: !model.updates.busy,
+                    #[track(model.updates.changed(Updates::input_mode()))]
+                    set_visible: model.updates.input_mode == InputMode::Secret,
+                    #[track(model.updates.changed(Updates::input()))]
+                    set_text: &model.updates.input,
+                    connect_activate[
+                        sender
+                    ] => move |this| {
+                        sender.input(Self::Input::Login {
+                            input: this.text().to_string(),
+                        })
+                    }
+                },
+                #[template_child]
+                visible_entry {
+                    #[track(model.updates.changed(Updates::busy()))]
+                    set_sensitive: !model.updates.busy,
+                    #[track(model.updates.changed(Updates::input_mode()))]
+                    set_visible: model.updates.input_mode == InputMode::Visible,
                     #[track(model.updates.changed(Updates::input()))]
                     set_text: &model.updates.input,
                     connect_activate[
@@ -130,6 +1075,8 @@ impl AsyncComponent for Greeter {
                     }
                 },
                 #[template_child]
+                cancel_button { connect_clicked => Self::Input::Cancel },
+                #[template_child]
                 error_info {
                     #[track(model.updates.changed(Updates::error()))]
                     set_visible: model.updates.error.is_some(),
@@ -139,15 +1086,40 @@ impl AsyncComponent for Greeter {
                     #[track(model.updates.changed(Updates::error()))]
                     set_label: model.updates.error.as_ref().unwrap_or(&"".to_string()),
                 },
-                #[template_child]
-                reboot_button { connect_clicked => Self::Input::Reboot },
-                #[template_child]
-                poweroff_button { connect_clicked => Self::Input::PowerOff },
             }
         }
     }
 
     fn post_view() {
+        if model.updates.changed(Updates::users()) {
+            setup_users(self, &widgets.ui.usernames_box);
+        } else if model.updates.changed(Updates::active_user_id()) {
+            select_user(self, &widgets.ui.usernames_box);
+        }
+        if model.updates.changed(Updates::sessions()) {
+            setup_sessions(self, &widgets.ui.sessions_list);
+        }
+        if model.updates.changed(Updates::sess_icon()) {
+            set_session_icon(&widgets.ui.session_icon, model.updates.sess_icon.as_deref());
+        }
+        if model.updates.changed(Updates::screensaver()) {
+            set_screensaver_css(&widgets.window, model.updates.screensaver);
+        }
+        if model.updates.changed(Updates::input_mode())
+            || (model.updates.changed(Updates::busy()) && !model.updates.busy)
+            || (model.updates.changed(Updates::banner()) && model.updates.banner.is_none())
+            || (model.updates.changed(Updates::screensaver())
+                && model.updates.screensaver == Screensaver::Off)
+        {
+            // Let the user directly type in the input requested by greetd. This is also needed
+            // after being busy, locked by the banner or blanked, since the entries lose focus
+            // while insensitive or hidden.
+            match model.updates.input_mode {
+                InputMode::Secret => widgets.ui.secret_entry.grab_focus(),
+                InputMode::Visible => widgets.ui.visible_entry.grab_focus(),
+                InputMode::None => false,
+            };
+        }
         if model.updates.changed(Updates::monitor()) {
             if let Some(monitor) = &model.updates.monitor {
                 widgets.window.fullscreen_on_monitor(monitor);
@@ -155,6 +1127,15 @@ impl AsyncComponent for Greeter {
                 setup_settings(self, &widgets.window);
             }
         }
+        if model.updates.changed(Updates::other_monitors()) {
+            setup_background_windows(self, &widgets.window, &sender);
+        }
+        if model
+            .updates
+            .changed(Updates::background_index() | Updates::monitor())
+        {
+            set_backgrounds(self, &widgets.window, &widgets.ui.background);
+        }
     }
 
     /// Initialize the greeter.
@@ -168,21 +1149,17 @@ impl AsyncComponent for Greeter {
 
         // cfg directives don't work inside Relm4 view! macro.
         #[cfg(feature = "gtk4_8")]
-        use crate::config::BgFit;
         widgets
             .ui
             .background
-            .set_content_fit(match model.config.get_background_fit() {
-                BgFit::Fill => gtk4::ContentFit::Fill,
-                BgFit::Contain => gtk4::ContentFit::Contain,
-                BgFit::Cover => gtk4::ContentFit::Cover,
-                BgFit::ScaleDown => gtk4::ContentFit::ScaleDown,
-            });
+            .set_content_fit(get_content_fit(&model));
 
         // Cancel any previous session, just in case someone started one.
         if let Err(err) = model.greetd_client.lock().await.cancel_session().await {
             warn!("Couldn't cancel greetd session: {err}");
         };
+        // Start a login attempt for the initial user.
+        model.recreate_session(&sender);
 
         model.choose_monitor(widgets.ui.display().name().as_str(), &sender);
         if let Some(monitor) = &model.updates.monitor {
@@ -193,11 +1170,27 @@ impl AsyncComponent for Greeter {
             // Couldn't choose a monitor, so let the compositor choose it for us.
             root.fullscreen();
         }
+        setup_background_windows(&model, &root, &sender);
+        set_backgrounds(&model, &root, &widgets.ui.background);
 
         // For some reason, the GTK settings are reset when changing monitors, so apply them after
         // full-screening.
         setup_settings(&model, &root);
-        setup_datetime_display(&sender);
+        setup_builtin_css(&model, &root);
+        setup_theme_css(&model, &root);
+        setup_layout(&model, &root, &widgets.ui);
+        setup_users(&model, &widgets.ui.usernames_box);
+        setup_sessions(&model, &widgets.ui.sessions_list);
+        setup_power_actions(&model, &widgets.ui.power_actions_box, &sender);
+        setup_reboot_menu(&model, &widgets.ui.power_actions_box, &sender);
+        // The icon theme is only known after applying the GTK settings.
+        set_session_icon(&widgets.ui.session_icon, model.updates.sess_icon.as_deref());
+        setup_datetime_display(&model, &sender);
+        setup_sys_watcher(&model, &sender);
+        setup_idle_watcher(&model, &sender);
+        setup_background_rotation(&model, &sender);
+        watch_activity(&root, &sender);
+        setup_banner(&model, &root, &sender);
 
         if input.css_path.exists() {
             debug!("Loading custom CSS from file: {}", input.css_path.display());
@@ -219,15 +1212,28 @@ impl AsyncComponent for Greeter {
         sender: AsyncComponentSender<Self>,
         _root: &Self::Root,
     ) {
-        debug!("Got input message: {msg:?}");
+        if !matches!(msg, Self::Input::Activity) {
+            debug!("Got input message: {msg:?}");
+        }
 
         // Reset the tracker for update changes.
         self.updates.reset();
 
         match msg {
-            Self::Input::Login { input } => self.login_click_handler(&sender, input).await,
-            Self::Input::Reboot => self.reboot_click_handler(&sender),
-            Self::Input::PowerOff => self.poweroff_click_handler(&sender),
+            Self::Input::Login { input } => self.login_click_handler(&sender, input),
+            Self::Input::UserChanged { user_id, user_text } => {
+                self.user_change_handler(&sender, user_id, user_text)
+            }
+            Self::Input::ToggleManualUser => self.toggle_manual_user_handler(&sender),
+            Self::Input::SessionChanged(session) => self.session_change_handler(session),
+            Self::Input::SessionCmdChanged(command) => self.session_cmd_change_handler(command),
+            Self::Input::ToggleManualSess => self.toggle_manual_sess_handler(),
+            Self::Input::Cancel => self.cancel_login_handler(&sender),
+            Self::Input::MonitorActivated(connector) => self.monitor_activated_handler(connector),
+            Self::Input::Activity => self.activity_handler(),
+            Self::Input::PowerAction(index) => self.power_action_handler(&sender, index),
+            Self::Input::RebootInto(entry_id) => self.reboot_into_handler(&sender, entry_id),
+            Self::Input::AcceptBanner => self.accept_banner_handler(&sender),
         }
     }
 
@@ -238,7 +1244,10 @@ impl AsyncComponent for Greeter {
         sender: AsyncComponentSender<Self>,
         _root: &Self::Root,
     ) {
-        if !matches!(msg, Self::CommandOutput::UpdateTime) {
+        if !matches!(
+            msg,
+            Self::CommandOutput::UpdateTime | Self::CommandOutput::IdleTick
+        ) {
             debug!("Got command message: {msg:?}");
         }
 
@@ -246,19 +1255,28 @@ impl AsyncComponent for Greeter {
         self.updates.reset();
 
         match msg {
-            Self::CommandOutput::UpdateTime => {
-                self.updates
-                    .set_date(Local::now().format(DATE_FMT).to_string());
-                self.updates
-                    .set_time(Local::now().format(TIME_FMT).to_string());
-            }
+            Self::CommandOutput::UpdateTime => self.update_time_handler(),
             Self::CommandOutput::ClearErr => self.updates.set_error(None),
+            Self::CommandOutput::IdleTick => self.idle_tick_handler(&sender),
+            Self::CommandOutput::RotateBackground => self.rotate_background_handler(),
+            Self::CommandOutput::CommandDone(error) => self.command_done_handler(&sender, error),
             Self::CommandOutput::HandleGreetdResponse(response) => {
-                self.handle_greetd_response(&sender, response).await
+                self.handle_greetd_response(&sender, response)
+            }
+            Self::CommandOutput::HandleNewSession(response) => {
+                self.new_session_handler(&sender, response)
             }
-            Self::CommandOutput::MonitorRemoved(display_name) => {
+            Self::CommandOutput::HandleStartSession(response) => {
+                self.start_session_handler(&sender, response)
+            }
+            Self::CommandOutput::SessionCancelled => self.updates.set_busy(false),
+            Self::CommandOutput::GreetdCallAborted => self.greetd_call_aborted_handler(&sender),
+            Self::CommandOutput::MonitorsChanged(display_name) => {
                 self.choose_monitor(display_name.as_str(), &sender)
             }
+            Self::CommandOutput::UpdateSysUtil(sys_util) => {
+                self.sys_util_change_handler(&sender, sys_util)
+            }
         };
     }
 }
@@ -8,6 +8,8 @@ use derivative::Derivative;
 use greetd_ipc::Response;
 use relm4::gtk::glib::GString;
 
+use crate::sysutil::SysUtil;
+
 #[derive(Debug)]
 /// Info about the current user and chosen session
 pub struct UserSessInfo {
@@ -30,20 +32,60 @@ pub enum InputMsg {
         #[derivative(Debug = "ignore")]
         input: String,
     },
-    Reboot,
-    PowerOff,
+    /// The user was changed in the dropdown, or the manually entered username was edited
+    UserChanged {
+        user_id: Option<GString>,
+        user_text: GString,
+    },
+    /// Toggle manual entry of the username
+    ToggleManualUser,
+    /// A session was chosen from the dropdown
+    SessionChanged(GString),
+    /// The manually entered session command was edited
+    SessionCmdChanged(GString),
+    /// Toggle manual entry of the session command
+    ToggleManualSess,
+    /// Cancel the login attempt
+    Cancel,
+    /// The pointer entered, or a key was pressed on, the monitor with this connector name
+    MonitorActivated(GString),
+    /// The user pressed a key, clicked or moved the pointer
+    Activity,
+    /// Run the power action (eg. Reboot) at this index in the config
+    PowerAction(usize),
+    /// Reboot into the boot entry with this ID, or into the firmware setup if there's no ID
+    RebootInto(Option<String>),
+    /// The legal banner was accepted
+    AcceptBanner,
 }
 
-#[derive(Debug)]
+#[derive(Derivative)]
+#[derivative(Debug)]
 /// The messages sent to the sender to run tasks in the background
 pub enum CommandMsg {
     /// Update the clock.
     UpdateTime,
     /// Clear the error message.
     ClearErr,
+    /// Check whether the greeter has been idle for too long.
+    IdleTick,
+    /// Show the next background image.
+    RotateBackground,
+    /// A command finished running, with an error message if it failed.
+    CommandDone(Option<String>),
     /// Handle a response received from greetd
     HandleGreetdResponse(Response),
-    /// Notify the greeter that a monitor was removed.
+    /// Handle the response received from greetd after creating a session
+    HandleNewSession(Response),
+    /// Handle the response received from greetd after starting the session
+    HandleStartSession(Response),
+    /// The greetd session was cancelled.
+    SessionCancelled,
+    /// A call to greetd was aborted, and the connection to greetd was reset.
+    GreetdCallAborted,
+    /// Notify the greeter that a monitor was added or removed.
     // The Gstring is the name of the display.
-    MonitorRemoved(GString),
+    MonitorsChanged(GString),
+    /// Replace the available users and sessions, since they changed on the system.
+    UpdateSysUtil(#[derivative(Debug = "ignore")] SysUtil),
 }
@@ -8,13 +8,23 @@
 
 //! The main logic for the greeter
 
-use std::path::Path;
-use std::process::Command;
+use std::fs::read_to_string;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use chrono::{
+    format::{Item, Numeric, StrftimeItems},
+    Local, Timelike,
+};
+use chrono_tz::Tz;
 use greetd_ipc::{AuthMessageType, ErrorType, Response};
-use gtk4::glib::GString;
+use gtk4::{
+    gio::ListModel,
+    glib::{markup_escape_text, GString, SignalHandlerId},
+};
+use rand::{thread_rng, Rng};
 use relm4::{
     gtk::{
         gdk::{Display, Monitor},
@@ -22,17 +32,26 @@ use relm4::{
     },
     AsyncComponentSender,
 };
-use tokio::{sync::Mutex, time::sleep};
+use tokio::{
+    process::Command,
+    sync::Mutex,
+    task::AbortHandle,
+    time::{sleep, timeout},
+};
 use tracing::{debug, error, info, instrument, warn};
 
 use crate::cache::Cache;
 use crate::client::{AuthStatus, GreetdClient};
-use crate::config::Config;
+use crate::config::{BannerSettings, BgOrder, ClockSettings, Config, IdleAction};
+use crate::constants::{AVATAR_PALETTE, DATE_FMT, TIME_FMT_12H, TIME_FMT_24H};
+use crate::issue::{expand_escapes, load_issue, IssueInfo};
 use crate::sysutil::SysUtil;
 
 use super::messages::{CommandMsg, UserSessInfo};
 
 const ERROR_MSG_CLEAR_DELAY: u64 = 5;
+/// Time (in seconds) after which a command that hasn't finished is killed
+const COMMAND_TIMEOUT: u64 = 30;
 
 #[derive(PartialEq)]
 pub(super) enum InputMode {
@@ -41,6 +60,17 @@ pub(super) enum InputMode {
     Visible,
 }
 
+/// How much of the greeter is shown, depending on how long it has been idle
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Screensaver {
+    /// Everything is shown normally.
+    Off,
+    /// Everything is shown, but dimmed.
+    Dimmed,
+    /// Only a black screen (and optionally the clock) is shown.
+    Blank,
+}
+
 // Fields only set by the model, that are meant to be read only by the widgets
 #[tracker::track]
 pub(super) struct Updates {
@@ -52,29 +82,190 @@ pub(super) struct Updates {
     pub(super) input: String,
     /// Whether the username is being entered manually
     pub(super) manual_user_mode: bool,
+    /// Usernames and full names of the users shown in the dropdown, sorted by full name
+    pub(super) users: Vec<(String, String)>,
+    /// Username of the user selected in the dropdown
+    pub(super) active_user_id: Option<String>,
     /// Whether the session is being entered manually
     pub(super) manual_sess_mode: bool,
     /// Input prompt sent by greetd for text input
     pub(super) input_prompt: String,
+    /// How the input requested by greetd should be entered
+    pub(super) input_mode: InputMode,
+    /// Names of the sessions shown in the dropdown, in sorted order
+    pub(super) sessions: Vec<String>,
     /// ID of the active session
     pub(super) active_session_id: Option<String>,
     /// Date that is displayed
     pub(super) date: String,
     /// Time that is displayed
     pub(super) time: String,
-    /// Monitor where the window is displayed
+    /// Time in the extra time zones that is displayed, one time zone per line
+    pub(super) timezones: String,
+    /// Monitor where the login UI is displayed
     pub(super) monitor: Option<Monitor>,
+    /// Monitors where only the background is displayed
+    pub(super) other_monitors: Vec<Monitor>,
+    /// Path to the avatar image of the current user
+    pub(super) avatar: Option<PathBuf>,
+    /// Initials of the current user, shown when there's no avatar image
+    pub(super) initials: String,
+    /// Index into the colour palette used behind the initials of the current user
+    pub(super) avatar_color: usize,
+    /// Name of the current session
+    pub(super) sess_name: String,
+    /// Icon name or path of the current session
+    pub(super) sess_icon: Option<String>,
+    /// Description of the current session
+    pub(super) sess_comment: Option<String>,
+    /// Whether a call to greetd is in progress
+    pub(super) busy: bool,
+    /// Warning shown before running the power action when the greeter is idle
+    pub(super) idle_warning: Option<String>,
+    /// State of the screensaver
+    pub(super) screensaver: Screensaver,
+    /// Text of the legal banner, until the user accepts it
+    pub(super) banner: Option<String>,
+    /// Issue file shown in the panel, with its escapes expanded
+    pub(super) issue: String,
+    /// Index of the background image that is shown, among the found background images
+    pub(super) background_index: usize,
 }
 
 /// Capitalize the first letter of the string.
 fn capitalize(string: &str) -> String {
-    string[0..1].to_uppercase() + &string[1..]
+    let mut chars = string.chars();
+    chars
+        .next()
+        .map(char::to_uppercase)
+        .into_iter()
+        .flatten()
+        .chain(chars)
+        .collect()
+}
+
+/// Get the initials of the first two words of a user's full name.
+fn get_initials(full_name: &str) -> String {
+    full_name
+        .split_whitespace()
+        .filter_map(|word| word.chars().next())
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// Pick a colour for a user's initials, such that it stays the same across logins.
+fn get_avatar_color(username: &str) -> usize {
+    username.bytes().map(usize::from).sum::<usize>() % AVATAR_PALETTE.len()
+}
+
+/// Get the usernames and full names of all users, sorted by full name.
+fn get_sorted_users(sys_util: &SysUtil) -> Vec<(String, String)> {
+    let mut users: Vec<(String, String)> = sys_util
+        .get_users()
+        .iter()
+        .map(|(full_name, username)| (username.clone(), full_name.clone()))
+        .collect();
+    users.sort_by(|(_, name1), (_, name2)| name1.cmp(name2));
+    users
+}
+
+/// Get the names of all sessions in sorted order.
+fn get_sorted_sessions(sys_util: &SysUtil) -> Vec<String> {
+    let mut sessions: Vec<String> = sys_util.get_sessions().keys().cloned().collect();
+    sessions.sort();
+    sessions
+}
+
+/// Check that a clock format is valid, and fall back to a default format if it isn't.
+fn validate_clock_format(format: &str, default: &str) -> String {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        warn!("Invalid clock format '{format}'; using '{default}' instead");
+        default.to_string()
+    } else {
+        format.to_string()
+    }
+}
+
+/// Get the text of the legal banner in the config, if any.
+fn load_banner(settings: &BannerSettings) -> Option<String> {
+    let path = if let Some(path) = &settings.path {
+        path
+    } else {
+        return settings.text.clone();
+    };
+    match read_to_string(path) {
+        Ok(text) => Some(text.trim_end().to_string()),
+        Err(err) => {
+            error!("Couldn't read banner file '{path}': {err}");
+            // Still require accepting a notice, so that a missing file doesn't bypass the banner.
+            Some(settings.text.clone().unwrap_or_else(|| {
+                "The notice that must be accepted before logging in couldn't be loaded.".to_string()
+            }))
+        }
+    }
+}
+
+/// Validated formats and time zones used by the clock
+pub(super) struct Clock {
+    /// Format of the date
+    date_fmt: String,
+    /// Format of the time
+    time_fmt: String,
+    /// Names (shown to the user) of extra time zones, with the time zones
+    timezones: Vec<(String, Tz)>,
+    /// Whether the clock shows seconds, and thus changes every second instead of every minute
+    pub(super) has_seconds: bool,
+}
+
+impl Clock {
+    fn new(settings: &ClockSettings) -> Self {
+        let date_fmt = validate_clock_format(&settings.date_format, DATE_FMT);
+        let time_fmt = validate_clock_format(
+            settings.get_time_format(),
+            if settings.twelve_hour {
+                TIME_FMT_12H
+            } else {
+                TIME_FMT_24H
+            },
+        );
+
+        let timezones = settings
+            .timezones
+            .iter()
+            .filter_map(|name| match name.parse::<Tz>() {
+                // Show only the city, such as "New York" for "America/New_York".
+                Ok(timezone) => Some((
+                    name.rsplit('/').next().unwrap_or(name).replace('_', " "),
+                    timezone,
+                )),
+                Err(err) => {
+                    warn!("Invalid time zone '{name}': {err}");
+                    None
+                }
+            })
+            .collect();
+
+        let has_seconds = [&date_fmt, &time_fmt].iter().any(|format| {
+            StrftimeItems::new(format)
+                .any(|item| matches!(item, Item::Numeric(Numeric::Second | Numeric::Timestamp, _)))
+        });
+
+        Self {
+            date_fmt,
+            time_fmt,
+            timezones,
+            has_seconds,
+        }
+    }
 }
 
 /// Greeter model that holds its state
 pub struct Greeter {
     /// Client to communicate with greetd
     pub(super) greetd_client: Arc<Mutex<GreetdClient>>,
+    /// Handle to abort the background call to greetd, while the UI is busy with it
+    greetd_call: Option<AbortHandle>,
     /// System utility to get available users and sessions
     pub(super) sys_util: SysUtil,
     /// The cache that persists between logins
@@ -87,53 +278,225 @@ pub struct Greeter {
     pub(super) updates: Updates,
     /// Is it run as demo
     pub(super) demo: bool,
+    /// When the user last used the greeter
+    pub(super) last_activity: Instant,
+    /// Formats and time zones used by the clock
+    pub(super) clock: Clock,
+    /// Hostname shown in the greeting
+    hostname: String,
+    /// Name of the OS shown in the greeting
+    os_name: String,
+    /// Whether the message shows the greeting, instead of a message from greetd
+    greeting_shown: bool,
+    /// Background images that are shown one at a time
+    pub(super) backgrounds: Vec<PathBuf>,
+    /// Text of the issue file (if any) with its escapes unexpanded, and the info they expand to
+    issue: Option<(String, IssueInfo)>,
+    /// Handler for monitors being added or removed, which is replaced when monitors are chosen again
+    monitors_handler: Option<(ListModel, SignalHandlerId)>,
 }
 
 impl Greeter {
     pub(super) async fn new(config_path: &Path, demo: bool) -> Self {
         let config = Config::new(config_path);
+        let mut cache = Cache::new();
+        let sysutil = SysUtil::new(config.get_user_settings(), config.get_session_settings())
+            .expect("Couldn't read available users and sessions");
+
+        // Show the last user initially, if they still exist. Otherwise, show the first user.
+        let users = get_sorted_users(&sysutil);
+        let initial_user = cache
+            .get_last_user()
+            .filter(|last_user| users.iter().any(|(username, _)| username == last_user))
+            .map(str::to_string)
+            .or_else(|| {
+                let first_user = users.first().map(|(username, _)| username.clone());
+                if let Some(user) = &first_user {
+                    info!("Using first found user '{user}' as initial user");
+                }
+                first_user
+            });
+
+        // Show the last session of the initial user, if it still exists. Otherwise, show the first
+        // session.
+        let sessions = get_sorted_sessions(&sysutil);
+        let initial_session = initial_user
+            .as_deref()
+            .and_then(|user| cache.get_last_session(user))
+            .filter(|last_session| sessions.iter().any(|session| session == last_session))
+            .map(str::to_string)
+            .or_else(|| sessions.first().cloned());
 
+        let clock = Clock::new(config.get_clock_settings());
+        let backgrounds = SysUtil::get_background_images(config.get_background_paths());
+        let issue = config
+            .get_issue_settings()
+            .path
+            .as_deref()
+            .and_then(|path| load_issue(Path::new(path)))
+            .map(|text| (text, IssueInfo::new()));
+        let background_index = match config.get_background_order() {
+            BgOrder::Random if !backgrounds.is_empty() => {
+                thread_rng().gen_range(0..backgrounds.len())
+            }
+            _ => 0,
+        };
         let updates = Updates {
-            message: config.get_default_message(),
+            // The greeting is shown once the model is created.
+            message: String::new(),
             error: None,
             input: String::new(),
-            manual_user_mode: false,
-            manual_sess_mode: false,
+            // Without any users to choose from, the username must be entered manually.
+            manual_user_mode: initial_user.is_none(),
+            users,
+            active_user_id: initial_user.clone(),
+            // Without any sessions to choose from, the session command must be entered manually.
+            manual_sess_mode: initial_session.is_none(),
+            sessions,
             input_prompt: String::new(),
-            active_session_id: None,
+            // Before a greetd session is created, assume that a secret (such as a password) will
+            // be needed.
+            input_mode: InputMode::Secret,
+            active_session_id: initial_session.clone(),
             tracker: 0,
             date: "".to_string(),
             time: "".to_string(),
+            timezones: String::new(),
             monitor: None,
+            other_monitors: Vec::new(),
+            avatar: None,
+            initials: String::new(),
+            avatar_color: 0,
+            sess_name: String::new(),
+            sess_icon: None,
+            sess_comment: None,
+            busy: false,
+            idle_warning: None,
+            screensaver: Screensaver::Off,
+            banner: load_banner(config.get_banner_settings()),
+            issue: String::new(),
+            background_index,
         };
         let greetd_client = Arc::new(Mutex::new(
             GreetdClient::new(demo)
                 .await
                 .expect("Couldn't initialize greetd client"),
         ));
-        let sysutil = SysUtil::new().expect("Couldn't read available users and sessions");
-        let default_user: String = sysutil.get_shells().keys().next().unwrap().to_string();
-        let default_session: String = sysutil.get_sessions().keys().next().unwrap().to_string();
         let sess_info = Some(UserSessInfo {
-            user_id: None,
-            user_text: GString::from(default_user),
-            sess_id: None,
-            sess_text: GString::from(default_session),
+            user_id: initial_user.map(GString::from),
+            user_text: GString::from(""),
+            sess_id: initial_session.map(GString::from),
+            sess_text: GString::from(""),
         });
         let mut greeter = Self {
             greetd_client,
+            greetd_call: None,
             sys_util: sysutil,
-            cache: Cache::new(),
-            sess_info: sess_info,
+            cache,
+            sess_info,
             config,
             updates,
             demo,
+            last_activity: Instant::now(),
+            clock,
+            hostname: SysUtil::get_hostname().unwrap_or_default(),
+            os_name: SysUtil::get_os_name().unwrap_or_else(|| "Linux".to_string()),
+            greeting_shown: true,
+            backgrounds,
+            issue,
+            monitors_handler: None,
         };
-        greeter.create_session().await;
+        greeter.update_avatar();
+        greeter.update_session_display();
+        greeter.show_greeting();
+        greeter.refresh_issue();
         greeter
     }
 
-    /// Make the greeter full screen over the first monitor.
+    /// Show the greeting message, after filling in its placeholders.
+    pub(super) fn show_greeting(&mut self) {
+        let now = Local::now();
+        let username = self.get_current_username().unwrap_or_default();
+        // Show the full name of the user, if they're chosen from the dropdown.
+        let user = self
+            .updates
+            .users
+            .iter()
+            .find(|(name, _)| *name == username)
+            .map_or(username.as_str(), |(_, full_name)| full_name.as_str());
+
+        let greeting = self
+            .config
+            .get_greeting_msg()
+            .replace(
+                "{greeting}",
+                self.config
+                    .get_time_greeting(now.hour())
+                    .unwrap_or_default(),
+            )
+            .replace("{user}", user)
+            .replace("{hostname}", &self.hostname)
+            .replace("{os_name}", &self.os_name)
+            .replace("{date}", &now.format(&self.clock.date_fmt).to_string());
+        self.updates.set_message(greeting);
+        self.greeting_shown = true;
+    }
+
+    /// Show a message from greetd instead of the greeting.
+    fn show_greetd_message(&mut self, message: String) {
+        self.updates.set_message(message);
+        self.greeting_shown = false;
+    }
+
+    /// Update the greeting message if it's shown, since its placeholders may have changed.
+    pub(super) fn refresh_greeting(&mut self) {
+        if self.greeting_shown {
+            self.show_greeting();
+        }
+    }
+
+    /// Expand the escapes in the issue file (if any) again, since the date and time in it change.
+    fn refresh_issue(&mut self) {
+        if let Some((text, info)) = &mut self.issue {
+            info.update_time();
+            // Trailing newlines are common in issue files, but only add empty space in a panel.
+            let issue = expand_escapes(text, info).trim_end().to_string();
+            self.updates.set_issue(issue);
+        }
+    }
+
+    /// Get the background image for the monitor with the given connector name.
+    ///
+    /// This is the image for that monitor in the config, otherwise the current image of the
+    /// slideshow.
+    pub(super) fn get_background(&self, connector: Option<&str>) -> Option<PathBuf> {
+        connector
+            .and_then(|connector| self.config.get_monitor_background(connector))
+            .map(PathBuf::from)
+            .or_else(|| self.backgrounds.get(self.updates.background_index).cloned())
+    }
+
+    /// Event handler for the interval between background images passing
+    ///
+    /// This shows the next background image, or a different random image.
+    pub(super) fn rotate_background_handler(&mut self) {
+        let count = self.backgrounds.len();
+        if count < 2 {
+            return;
+        }
+        let offset = match self.config.get_background_order() {
+            // Skip the current image, so that the background always changes.
+            BgOrder::Random => thread_rng().gen_range(1..count),
+            BgOrder::Sequential => 1,
+        };
+        self.updates
+            .set_background_index((self.updates.background_index + offset) % count);
+    }
+
+    /// Choose the monitor that shows the login UI, and the ones that only show the background.
+    ///
+    /// The login UI is shown on the primary monitor in the config, or on the first monitor if that
+    /// isn't connected. The monitors are chosen again whenever one is added or removed.
     #[instrument(skip(self, sender))]
     pub(super) fn choose_monitor(
         &mut self,
@@ -148,9 +511,21 @@ impl Greeter {
             }
         };
 
-        let mut chosen_monitor = None;
-        for monitor in display
-            .monitors()
+        // Don't keep the handler from the previous call, since handlers would pile up otherwise.
+        if let Some((monitor_list, handler)) = self.monitors_handler.take() {
+            monitor_list.disconnect(handler);
+        }
+        let monitor_list = display.monitors();
+        let handler_sender = sender.clone();
+        let display_name = display.name();
+        let handler = monitor_list.connect_items_changed(move |_, _, _, _| {
+            let display_name = display_name.clone();
+            handler_sender.oneshot_command(async move { CommandMsg::MonitorsChanged(display_name) })
+        });
+        self.monitors_handler = Some((monitor_list.clone(), handler));
+
+        let mut monitors = Vec::new();
+        for monitor in monitor_list
             .into_iter()
             .filter_map(|item| {
                 item.ok()
@@ -159,85 +534,383 @@ impl Greeter {
             .filter(Monitor::is_valid)
         {
             debug!("Found monitor: {monitor}");
-            let sender = sender.clone();
-            monitor.connect_invalidate(move |monitor| {
-                let display_name = monitor.display().name();
-                sender.oneshot_command(async move { CommandMsg::MonitorRemoved(display_name) })
-            });
-            if chosen_monitor.is_none() {
-                // Choose the first monitor.
-                chosen_monitor = Some(monitor);
-            }
+            monitors.push(monitor);
         }
 
+        let settings = self.config.get_monitor_settings();
+        let find_monitor = |connector: &str| {
+            monitors
+                .iter()
+                .position(|monitor| monitor.connector().as_deref() == Some(connector))
+        };
+
+        // When following the pointer, keep the login UI where it was, if that monitor is still
+        // connected.
+        let current_index = if settings.follow_pointer {
+            self.updates
+                .monitor
+                .as_ref()
+                .and_then(Monitor::connector)
+                .and_then(|connector| find_monitor(&connector))
+        } else {
+            None
+        };
+
+        let primary_index = current_index.or_else(|| {
+            let connector = settings.primary.as_ref()?;
+            let index = find_monitor(connector);
+            if index.is_none() {
+                warn!("Primary monitor not found: {connector}");
+            }
+            index
+        });
+
+        let chosen_monitor = if monitors.is_empty() {
+            None
+        } else {
+            // Choose the first monitor if the primary one isn't found.
+            Some(monitors.remove(primary_index.unwrap_or(0)))
+        };
         self.updates.set_monitor(chosen_monitor);
+        self.updates.set_other_monitors(monitors);
     }
 
-    /// Run a command and log any errors in a background thread.
-    fn run_cmd(command: &[String], sender: &AsyncComponentSender<Self>) {
-        let mut process = Command::new(&command[0]);
-        process.args(command[1..].iter());
-        // Run the command and check its output in a separate thread, so as to not block the GUI.
-        sender.spawn_command(move |_| match process.output() {
-            Ok(output) => {
-                if !output.status.success() {
-                    if let Ok(err) = std::str::from_utf8(&output.stderr) {
-                        error!("Failed to launch command: {err}")
+    /// Event handler for the pointer entering, or a key being pressed on, a monitor that only shows
+    /// the background
+    ///
+    /// This moves the login UI to that monitor, and shows only the background on the previous one.
+    pub(super) fn monitor_activated_handler(&mut self, connector: GString) {
+        let index = if let Some(index) = self
+            .updates
+            .other_monitors
+            .iter()
+            .position(|monitor| monitor.connector().as_ref() == Some(&connector))
+        {
+            index
+        } else {
+            // The monitor was probably removed in the meantime.
+            return;
+        };
+        info!("Moving the login UI to monitor: {connector}");
+
+        let mut other_monitors = self.updates.other_monitors.clone();
+        let chosen_monitor = other_monitors.remove(index);
+        if let Some(monitor) = self.updates.monitor.take() {
+            other_monitors.push(monitor);
+        }
+        self.updates.set_monitor(Some(chosen_monitor));
+        self.updates.set_other_monitors(other_monitors);
+    }
+
+    /// Run the command of a power action in the background, and report whether it failed.
+    ///
+    /// The command is killed if it doesn't finish within [`COMMAND_TIMEOUT`] seconds.
+    fn run_cmd(&mut self, sender: &AsyncComponentSender<Self>, label: &str, command: &[String]) {
+        let (program, args) = match (command.split_first(), SysUtil::check_command(command)) {
+            (Some(split), Ok(())) => split,
+            (_, Err(reason)) => {
+                // The button should've been disabled, but the command may have been removed since.
+                self.display_error(
+                    sender,
+                    &reason,
+                    &format!("Can't run power action '{label}': {reason}"),
+                );
+                return;
+            }
+            (None, Ok(())) => unreachable!("Empty commands are rejected by the check"),
+        };
+        if self.demo {
+            info!("demo: skip power action: {label}");
+            return;
+        }
+        info!("Running power action: {label}");
+
+        let mut process = Command::new(program);
+        process.args(args).kill_on_drop(true);
+        // Run the command and check its output in the background, so as to not block the GUI.
+        sender.oneshot_command(async move {
+            let output = timeout(Duration::from_secs(COMMAND_TIMEOUT), process.output()).await;
+            let error = match output {
+                Ok(Ok(output)) if output.status.success() => None,
+                Ok(Ok(output)) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                    if stderr.is_empty() {
+                        Some(format!("Command exited with {}", output.status))
                     } else {
-                        error!("Failed to launch command: {:?}", output.stderr)
+                        Some(stderr)
                     }
                 }
-            }
-            Err(err) => error!("Failed to launch command: {err}"),
+                Ok(Err(err)) => Some(err.to_string()),
+                Err(_) => Some(format!("Command timed out after {COMMAND_TIMEOUT}s")),
+            };
+            CommandMsg::CommandDone(error)
         });
     }
 
-    /// Event handler for clicking the "Reboot" button
+    /// Event handler for a command finishing
+    pub(super) fn command_done_handler(
+        &mut self,
+        sender: &AsyncComponentSender<Self>,
+        error: Option<String>,
+    ) {
+        if let Some(error) = error {
+            self.display_error(
+                sender,
+                &capitalize(&error),
+                &format!("Failed to launch command: {error}"),
+            );
+        }
+    }
+
+    /// Event handler for clicking a power action button (eg. "Reboot"), after confirmation if
+    /// needed
     ///
-    /// This reboots the PC.
-    #[instrument(skip_all)]
-    pub(super) fn reboot_click_handler(&self, sender: &AsyncComponentSender<Self>) {
-        if self.demo {
-            info!("demo: skip reboot");
+    /// This runs the command of the power action.
+    #[instrument(skip(self, sender))]
+    pub(super) fn power_action_handler(
+        &mut self,
+        sender: &AsyncComponentSender<Self>,
+        index: usize,
+    ) {
+        let actions = self.config.get_sys_commands().get_actions();
+        let action = if let Some(action) = actions.get(index) {
+            action
+        } else {
+            error!("Invalid power action index: {index}");
             return;
-        }
-        info!("Rebooting");
-        Self::run_cmd(&self.config.get_sys_commands().reboot, sender);
+        };
+        self.run_cmd(sender, &action.label, &action.command);
     }
 
-    /// Event handler for clicking the "Power-Off" button
+    /// Event handler for choosing an entry in the reboot menu, after confirmation
     ///
-    /// This shuts down the PC.
-    #[instrument(skip_all)]
-    pub(super) fn poweroff_click_handler(&self, sender: &AsyncComponentSender<Self>) {
-        if self.demo {
-            info!("demo: skip shutdown");
+    /// This reboots into the boot entry with the given ID, or into the firmware setup if there's
+    /// no ID.
+    #[instrument(skip(self, sender))]
+    pub(super) fn reboot_into_handler(
+        &mut self,
+        sender: &AsyncComponentSender<Self>,
+        entry_id: Option<String>,
+    ) {
+        let settings = self.config.get_boot_settings();
+        let (label, command) = if let Some(entry_id) = &entry_id {
+            let command = settings
+                .entry_command
+                .iter()
+                .map(|arg| arg.replace("{id}", entry_id))
+                .collect();
+            (format!("reboot into {entry_id}"), command)
+        } else {
+            (
+                "reboot into firmware setup".to_string(),
+                settings.firmware_command.clone(),
+            )
+        };
+        self.run_cmd(sender, &label, &command);
+    }
+
+    /// Event handler for any input from the user
+    ///
+    /// This wakes up the screen, and postpones the power action run when the greeter is idle.
+    pub(super) fn activity_handler(&mut self) {
+        self.last_activity = Instant::now();
+        self.updates.set_idle_warning(None);
+        self.updates.set_screensaver(Screensaver::Off);
+    }
+
+    /// Check how long the greeter has been idle, and update the screensaver and the idle power
+    /// action accordingly.
+    pub(super) fn idle_tick_handler(&mut self, sender: &AsyncComponentSender<Self>) {
+        self.update_idle_action(sender);
+        self.update_screensaver();
+    }
+
+    /// Check whether the clock is shown, which it isn't on the blank screen unless configured.
+    pub(super) fn shows_clock(&self) -> bool {
+        self.config.get_clock_settings().enabled
+            && (self.updates.screensaver != Screensaver::Blank
+                || self.config.get_screensaver_settings().show_clock)
+    }
+
+    /// Show the current date and time in the clock (if enabled), and in the greeting and the issue
+    /// file if needed.
+    pub(super) fn update_time_handler(&mut self) {
+        self.refresh_greeting();
+        self.refresh_issue();
+        if !self.config.get_clock_settings().enabled {
             return;
         }
-        info!("Shutting down");
-        Self::run_cmd(&self.config.get_sys_commands().poweroff, sender);
+
+        let now = Local::now();
+        let date = now.format(&self.clock.date_fmt).to_string();
+        let time = now.format(&self.clock.time_fmt).to_string();
+        let timezones = self
+            .clock
+            .timezones
+            .iter()
+            .map(|(name, timezone)| {
+                let time = now.with_timezone(timezone).format(&self.clock.time_fmt);
+                format!("{name}  {time}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.updates
+            .set_date(format!("<b>{}</b>", markup_escape_text(&date)));
+        self.updates
+            .set_time(format!("<b><big>{}</big></b>", markup_escape_text(&time)));
+        self.updates.set_timezones(timezones);
     }
 
-    /// Event handler for clicking the "Cancel" button
-    ///
-    /// This cancels the created session and goes back to the user/session chooser.
-    #[instrument(skip_all)]
-    pub(super) async fn cancel_click_handler(&mut self) {
-        if let Err(err) = self.greetd_client.lock().await.cancel_session().await {
-            warn!("Couldn't cancel greetd session: {err}");
+    /// Dim or blank the screen if the greeter has been idle for long enough.
+    fn update_screensaver(&mut self) {
+        let settings = self.config.get_screensaver_settings();
+        let idle_time = self.last_activity.elapsed().as_secs();
+        let reached = |timeout: Option<u64>| timeout.map_or(false, |timeout| idle_time >= timeout);
+
+        let screensaver = if reached(settings.blank_timeout) && self.updates.idle_warning.is_none()
+        {
+            Screensaver::Blank
+        } else if reached(settings.dim_timeout) || reached(settings.blank_timeout) {
+            // Keep the warning about the idle power action visible.
+            Screensaver::Dimmed
+        } else {
+            Screensaver::Off
         };
-        self.updates.set_input(String::new());
-        self.updates.set_message(self.config.get_default_message())
+        self.updates.set_screensaver(screensaver);
     }
 
-    /// Create a greetd session, i.e. start a login attempt for the current user.
-    async fn create_session(&mut self) {
-        let username = if let Some(username) = self.get_current_username() {
-            username
+    /// Warn about or run the idle power action, if the greeter has been idle for long enough.
+    fn update_idle_action(&mut self, sender: &AsyncComponentSender<Self>) {
+        let settings = self.config.get_idle_settings();
+        let (timeout, action) = if let Some(timeout) = settings.timeout {
+            (timeout, settings.action)
         } else {
-            // No username found (which shouldn't happen), so we can't create the session.
             return;
         };
+        let remaining = timeout.saturating_sub(self.last_activity.elapsed().as_secs());
+        let (label, verb) = match action {
+            IdleAction::Reboot => ("reboot", "Rebooting"),
+            IdleAction::PowerOff => ("power off", "Shutting down"),
+            IdleAction::Suspend => ("suspend", "Suspending"),
+            IdleAction::Hibernate => ("hibernate", "Hibernating"),
+        };
+
+        if remaining == 0 {
+            // Start a new idle period, so that the action runs again only after another timeout.
+            self.last_activity = Instant::now();
+            self.updates.set_idle_warning(None);
+            let command = self
+                .config
+                .get_sys_commands()
+                .get_idle_command(action)
+                .to_vec();
+            info!("Greeter was idle for {timeout}s");
+            self.run_cmd(sender, label, &command);
+        } else if remaining <= settings.warning {
+            self.updates.set_idle_warning(Some(format!(
+                "{verb} in {remaining}s. Press any key or move the pointer to cancel."
+            )));
+        } else {
+            self.updates.set_idle_warning(None);
+        }
+    }
+
+    /// Go back to the user/session chooser, after the login attempt was cancelled.
+    fn reset_login_ui(&mut self) {
+        self.updates.set_input(String::new());
+        self.updates.set_input_prompt(String::new());
+        self.updates.set_input_mode(InputMode::Secret);
+        self.show_greeting();
+    }
+
+    /// Cancel the created greetd session, and go back to the user/session chooser.
+    fn cancel_session(&mut self, sender: &AsyncComponentSender<Self>) {
+        self.reset_login_ui();
+
+        let client = Arc::clone(&self.greetd_client);
+        self.call_greetd(sender, async move {
+            if let Err(err) = client.lock().await.cancel_session().await {
+                warn!("Couldn't cancel greetd session: {err}");
+            };
+            CommandMsg::SessionCancelled
+        });
+    }
+
+    /// Event handler for pressing Escape or clicking the "Cancel" button during a login attempt
+    ///
+    /// This cancels the login attempt and starts a new one for the chosen user. This also works
+    /// while greetd is handling a request, such as waiting for a fingerprint.
+    #[instrument(skip_all)]
+    pub(super) fn cancel_login_handler(&mut self, sender: &AsyncComponentSender<Self>) {
+        self.updates.set_error(None);
+        self.recreate_session(sender);
+    }
+
+    /// Call greetd in the background, and keep the UI busy until the response arrives.
+    ///
+    /// greetd can't be asked to cancel a request that it's still handling, so the call can instead
+    /// be aborted with [`Self::abort_greetd_call`].
+    fn call_greetd<F>(&mut self, sender: &AsyncComponentSender<Self>, call: F)
+    where
+        F: Future<Output = CommandMsg> + Send + 'static,
+    {
+        self.updates.set_busy(true);
+        let task = relm4::spawn(call);
+        self.greetd_call = Some(task.abort_handle());
+
+        let client = Arc::clone(&self.greetd_client);
+        let demo = self.demo;
+        sender.oneshot_command(async move {
+            match task.await {
+                Ok(msg) => msg,
+                Err(err) if err.is_cancelled() => {
+                    // The connection might be left in the middle of a request, so replace it.
+                    // greetd cancels the session of the old connection once it's closed.
+                    *client.lock().await = GreetdClient::new(demo)
+                        .await
+                        .expect("Couldn't reconnect to greetd");
+                    CommandMsg::GreetdCallAborted
+                }
+                Err(err) => std::panic::resume_unwind(err.into_panic()),
+            }
+        });
+    }
+
+    /// Abort the background call to greetd, if any.
+    fn abort_greetd_call(&mut self) {
+        if let Some(call) = self.greetd_call.take() {
+            info!("Aborting the pending call to greetd");
+            call.abort();
+        }
+    }
+
+    /// Event handler for an aborted call to greetd, after the connection to greetd was reset
+    ///
+    /// The login attempt was cancelled along with the old connection, so this starts a new one.
+    pub(super) fn greetd_call_aborted_handler(&mut self, sender: &AsyncComponentSender<Self>) {
+        self.updates.set_busy(false);
+        self.recreate_session(sender);
+    }
+
+    /// Get the authentication status of the greetd session.
+    ///
+    /// This must only be called while the UI isn't busy, so that no background call to greetd is
+    /// using the client.
+    fn get_auth_status(&self) -> AuthStatus {
+        self.greetd_client
+            .try_lock()
+            .expect("greetd client is in use while the UI isn't busy")
+            .get_auth_status()
+            .clone()
+    }
+
+    /// Get the username for which a greetd session should be created.
+    ///
+    /// This returns `None` if the current selections can't be used to create a session.
+    fn get_session_username(&self) -> Option<String> {
+        // Without a username (eg. an empty manual entry), we can't create the session.
+        let username = self.get_current_username()?;
 
         // Before trying to create a session, check if the session command (if manually entered) is
         // valid.
@@ -245,25 +918,87 @@ impl Greeter {
             let info = self.sess_info.as_ref().expect("No session info set yet");
             if shlex::split(info.sess_text.as_str()).is_none() {
                 // This must be an invalid command.
-                println!("Invalid session command: {}", info.sess_text);
-                return;
+                warn!("Invalid session command: {}", info.sess_text);
+                return None;
             };
             debug!("Manually entered session command is parsable");
         };
 
+        Some(username)
+    }
+
+    /// Create a greetd session, i.e. start a login attempt for the given user.
+    ///
+    /// Any previously created session is cancelled first. If an input is given, then it is used to
+    /// answer the first prompt of the new session.
+    fn create_session(
+        &mut self,
+        sender: &AsyncComponentSender<Self>,
+        username: String,
+        input: Option<String>,
+    ) {
         info!("Creating session for user: {username}");
 
-        // Create a session for the current user.
-        let response = self
-            .greetd_client
-            .lock()
-            .await
-            .create_session(&username)
-            .await
-            .unwrap_or_else(|err| {
-                panic!("Failed to create session for username '{username}': {err}",)
-            });
-        info!("Response: {:?}", response);
+        let client = Arc::clone(&self.greetd_client);
+        self.call_greetd(sender, async move {
+            let mut client = client.lock().await;
+            // Cancel in the same background call, so that it can't happen after the creation.
+            if !matches!(client.get_auth_status(), AuthStatus::NotStarted) {
+                if let Err(err) = client.cancel_session().await {
+                    warn!("Couldn't cancel greetd session: {err}");
+                };
+            }
+            let response = client
+                .create_session(&username)
+                .await
+                .unwrap_or_else(|err| {
+                    panic!("Failed to create session for username '{username}': {err}",)
+                });
+            info!("Response: {:?}", response);
+
+            match (response, input) {
+                // The input was entered before the session was created, so use it to answer the
+                // first prompt.
+                (Response::AuthMessage { .. }, Some(input)) => {
+                    let response = client
+                        .send_auth_response(Some(input))
+                        .await
+                        .unwrap_or_else(|err| panic!("Failed to send input: {err}"));
+                    CommandMsg::HandleGreetdResponse(response)
+                }
+                (response, Some(_)) => CommandMsg::HandleGreetdResponse(response),
+                (response, None) => CommandMsg::HandleNewSession(response),
+            }
+        });
+    }
+
+    /// Event handler for accepting the legal banner
+    ///
+    /// This unlocks the login UI, and starts a login attempt for the chosen user.
+    #[instrument(skip_all)]
+    pub(super) fn accept_banner_handler(&mut self, sender: &AsyncComponentSender<Self>) {
+        if self.updates.banner.is_none() {
+            return;
+        }
+        info!("Legal banner accepted at {}", Local::now().to_rfc3339());
+        self.updates.set_banner(None);
+        self.recreate_session(sender);
+    }
+
+    /// Event handler for the response from greetd after creating a session without any input
+    pub(super) fn new_session_handler(
+        &mut self,
+        sender: &AsyncComponentSender<Self>,
+        response: Response,
+    ) {
+        if let Response::Success = response {
+            // Don't log in without the user's confirmation, even if no authentication is needed.
+            // The session is started on the next login attempt.
+            info!("No authentication needed for current user");
+            self.updates.set_busy(false);
+        } else {
+            self.handle_greetd_response(sender, response);
+        }
     }
 
     /// This function handles a greetd response as follows:
@@ -278,18 +1013,21 @@ impl Greeter {
     /// This way of handling responses allows for composite authentication procedures, e.g.:
     /// 1. Fingerprint
     /// 2. Password
-    pub(super) async fn handle_greetd_response(
+    pub(super) fn handle_greetd_response(
         &mut self,
         sender: &AsyncComponentSender<Self>,
         response: Response,
     ) {
+        // The background call to greetd is done, so the UI can be used again.
+        self.updates.set_busy(false);
+
         match response {
             Response::Success => {
                 // Authentication was successful and the session may be started.
                 // This may happen on the first request, in which case logging in
                 // as the given user requires no authentication.
                 info!("Successfully logged in; starting session");
-                self.start_session(sender).await;
+                self.start_session(sender);
                 return;
             }
             Response::AuthMessage {
@@ -301,6 +1039,7 @@ impl Greeter {
                         // Greetd has requested input that should be hidden
                         // e.g.: a password
                         info!("greetd asks for a secret auth input: {auth_message}");
+                        self.updates.set_input_mode(InputMode::Secret);
                         self.updates.set_input(String::new());
                         self.updates
                             .set_input_prompt(auth_message.trim_end().to_string());
@@ -309,6 +1048,7 @@ impl Greeter {
                     AuthMessageType::Visible => {
                         // Greetd has requested input that need not be hidden
                         info!("greetd asks for a visible auth input: {auth_message}");
+                        self.updates.set_input_mode(InputMode::Visible);
                         self.updates.set_input(String::new());
                         self.updates
                             .set_input_prompt(auth_message.trim_end().to_string());
@@ -318,12 +1058,14 @@ impl Greeter {
                         // Greetd has sent an info message that should be displayed
                         // e.g.: asking for a fingerprint
                         info!("greetd sent an info: {auth_message}");
-                        self.updates.set_message(auth_message);
+                        self.updates.set_input_mode(InputMode::None);
+                        self.show_greetd_message(auth_message);
                     }
                     AuthMessageType::Error => {
                         // Greetd has sent an error message that should be displayed and logged
                         // Reset outdated info message, if any
-                        self.updates.set_message(self.config.get_default_message());
+                        self.updates.set_input_mode(InputMode::None);
+                        self.show_greeting();
                         self.display_error(
                             sender,
                             &capitalize(&auth_message),
@@ -343,17 +1085,17 @@ impl Greeter {
                     &format!("Error from greetd: {description}"),
                 );
 
-                // In case this is an authentication error (e.g. wrong password), the session should be cancelled.
+                // In case this is an authentication error (e.g. wrong password), the session should be
+                // cancelled, and a new login attempt started.
                 if let ErrorType::AuthError = error_type {
-                    self.cancel_click_handler().await
+                    self.recreate_session(sender)
                 }
                 return;
             }
         }
 
-        debug!("Sending empty auth response to greetd");
         let client = Arc::clone(&self.greetd_client);
-        sender.oneshot_command(async move {
+        self.call_greetd(sender, async move {
             debug!("Sending empty auth response to greetd");
             let response = client
                 .lock()
@@ -365,106 +1107,367 @@ impl Greeter {
         });
     }
 
-    /// Event handler for selecting a different username in the `ComboBoxText`
+    /// Event handler for selecting a different username in the `ComboBoxText`, or for editing the
+    /// manually entered username
     ///
-    /// This changes the session in the combo box according to the last used session of the current user.
+    /// This restarts the greetd session for the new user, and changes the session in the combo box
+    /// according to the last used session of the new user.
     #[instrument(skip_all)]
-    pub(super) fn user_change_handler(&mut self) {
+    pub(super) fn user_change_handler(
+        &mut self,
+        sender: &AsyncComponentSender<Self>,
+        user_id: Option<GString>,
+        user_text: GString,
+    ) {
+        let info = self.sess_info.as_mut().expect("No session info set yet");
+        if info.user_id == user_id && info.user_text == user_text {
+            // This happens when the view re-selects the current user, so nothing changed.
+            return;
+        }
+        if user_id.is_none() && !self.updates.manual_user_mode {
+            // The dropdown is being repopulated, and the user will be re-selected afterwards.
+            return;
+        }
+        info.user_id = user_id;
+        info.user_text = user_text;
+
+        self.recreate_session(sender);
+        self.user_selection_changed();
+    }
+
+    /// Event handler for toggling manual entry of the username
+    #[instrument(skip_all)]
+    pub(super) fn toggle_manual_user_handler(&mut self, sender: &AsyncComponentSender<Self>) {
+        if self.updates.manual_user_mode && self.updates.users.is_empty() {
+            // There are no users to choose from in the dropdown.
+            return;
+        }
+        self.updates
+            .set_manual_user_mode(!self.updates.manual_user_mode);
+        self.recreate_session(sender);
+        self.user_selection_changed();
+    }
+
+    /// Cancel the greetd session and create one for the current user.
+    ///
+    /// When the username is entered manually, the session is only created on login, since the
+    /// username might be incomplete till then. No session is created until the legal banner (if
+    /// any) is accepted.
+    pub(super) fn recreate_session(&mut self, sender: &AsyncComponentSender<Self>) {
+        if self.updates.busy {
+            // greetd can't be interrupted while it's handling a request, so abort the call. The
+            // session is recreated once the connection to greetd is reset.
+            self.abort_greetd_call();
+            self.reset_login_ui();
+            return;
+        }
+        let started = !matches!(self.get_auth_status(), AuthStatus::NotStarted);
+
+        let username = if self.updates.manual_user_mode || self.updates.banner.is_some() {
+            None
+        } else {
+            self.get_session_username()
+        };
+        if let Some(username) = username {
+            if started {
+                self.reset_login_ui();
+            }
+            // This also cancels the previous session.
+            self.create_session(sender, username, None);
+        } else if started {
+            self.cancel_session(sender);
+        }
+    }
+
+    /// Update the user-specific parts of the UI, after the current user changed.
+    fn user_selection_changed(&mut self) {
         let username = if let Some(username) = self.get_current_username() {
             username
         } else {
-            // No username found (which shouldn't happen), so we can't change the session.
+            // No username found (eg. empty manual entry), so we can't change the session.
             return;
         };
+        self.update_avatar();
+        self.refresh_greeting();
 
         if let Some(last_session) = self.cache.get_last_session(&username) {
-            // Set the last session used by this user in the session combo box.
-            self.updates
-                .set_active_session_id(Some(last_session.to_string()));
+            // Set the last session used by this user in the session dropdown.
+            let last_session = last_session.to_string();
+            if self.updates.sessions.contains(&last_session) {
+                self.select_session(last_session);
+            } else {
+                info!("Last session '{last_session}' for user '{username}' is unavailable");
+            }
         } else {
             // Last session not found, so skip changing the session.
             info!("Last session for user '{username}' missing");
         };
     }
 
+    /// Event handler for choosing a session in the dropdown
+    #[instrument(skip_all)]
+    pub(super) fn session_change_handler(&mut self, session: GString) {
+        debug!("Chose session: {session}");
+        self.select_session(session.to_string());
+    }
+
+    /// Event handler for editing the manually entered session command
+    #[instrument(skip_all)]
+    pub(super) fn session_cmd_change_handler(&mut self, command: GString) {
+        let info = self.sess_info.as_mut().expect("No session info set yet");
+        info.sess_text = command;
+    }
+
+    /// Event handler for toggling manual entry of the session command
+    #[instrument(skip_all)]
+    pub(super) fn toggle_manual_sess_handler(&mut self) {
+        if self.updates.manual_sess_mode && self.updates.sessions.is_empty() {
+            // There are no sessions to choose from in the dropdown.
+            return;
+        }
+        self.updates
+            .set_manual_sess_mode(!self.updates.manual_sess_mode);
+    }
+
+    /// Set the session chosen in the dropdown.
+    fn select_session(&mut self, session: String) {
+        let info = self.sess_info.as_mut().expect("No session info set yet");
+        info.sess_id = Some(GString::from(session.as_str()));
+        self.updates.set_active_session_id(Some(session));
+        self.update_session_display();
+    }
+
+    /// Event handler for changes to the available users and sessions on the system
+    ///
+    /// This keeps the current user and session selected if they still exist, otherwise it falls
+    /// back to the first available ones.
+    #[instrument(skip_all)]
+    pub(super) fn sys_util_change_handler(
+        &mut self,
+        sender: &AsyncComponentSender<Self>,
+        sys_util: SysUtil,
+    ) {
+        self.sys_util = sys_util;
+        self.updates.set_users(get_sorted_users(&self.sys_util));
+        let info = self.sess_info.as_mut().expect("No session info set yet");
+
+        let mut user_removed = false;
+        let user_exists = |user_id: &GString| {
+            self.updates
+                .users
+                .iter()
+                .any(|(username, _)| username == user_id.as_str())
+        };
+        let selected_exists = info.user_id.as_ref().map_or(false, user_exists);
+        if !selected_exists && (info.user_id.is_some() || !self.updates.users.is_empty()) {
+            let first_user = self.updates.users.first().map(|(user, _)| user.clone());
+            info!(
+                "Selected user {:?} is unavailable; selecting {first_user:?}",
+                info.user_id
+            );
+            info.user_id = first_user.clone().map(GString::from);
+            self.updates.set_active_user_id(first_user);
+            user_removed = !self.updates.manual_user_mode;
+        }
+
+        self.updates
+            .set_sessions(get_sorted_sessions(&self.sys_util));
+        let session_exists = info.sess_id.as_ref().map_or(false, |sess_id| {
+            self.sys_util.get_sessions().contains_key(sess_id.as_str())
+        });
+        if !session_exists && (info.sess_id.is_some() || !self.updates.sessions.is_empty()) {
+            let first_session = self.updates.sessions.first().cloned();
+            info!(
+                "Selected session {:?} is unavailable; selecting {first_session:?}",
+                info.sess_id
+            );
+            info.sess_id = first_session.clone().map(GString::from);
+            self.updates.set_active_session_id(first_session);
+        }
+
+        // The avatar or session details might have changed, even if the selection didn't.
+        self.update_avatar();
+        self.update_session_display();
+
+        if user_removed {
+            // The greetd session belongs to the removed user, so restart it for the new one.
+            self.recreate_session(sender);
+            self.user_selection_changed();
+        }
+    }
+
     /// Event handler for clicking the "Login" button
     ///
     /// This does one of the following, depending of the state of authentication:
     ///     - Begins a login attempt for the given user
     ///     - Submits the entered password for logging in and starts the session
     #[instrument(skip_all)]
-    pub(super) async fn login_click_handler(
+    pub(super) fn login_click_handler(
         &mut self,
         sender: &AsyncComponentSender<Self>,
         input: String,
     ) {
+        if self.updates.busy {
+            // The previous login attempt is still in progress.
+            return;
+        }
+        if self.updates.banner.is_some() {
+            // Credentials can't be entered before accepting the legal banner.
+            return;
+        }
+
         // Check if a password is needed. If not, then directly start the session.
-        let auth_status = self.greetd_client.lock().await.get_auth_status().clone();
-        match auth_status {
+        match self.get_auth_status() {
             AuthStatus::Done => {
                 // No password is needed, but the session should've been already started by
                 // `create_session`.
                 warn!("No password needed for current user, but session not already started");
-                self.start_session(sender).await;
+                self.start_session(sender);
             }
             AuthStatus::InProgress => {
-                self.send_input(sender, input).await;
+                self.send_input(sender, input);
             }
             AuthStatus::NotStarted => {
-                self.create_session().await;
-                self.send_input(sender, input).await;
+                if let Some(username) = self.get_session_username() {
+                    // Reset the password field, since the input is sent along with the session.
+                    self.updates.set_input(String::new());
+                    self.create_session(sender, username, Some(input));
+                } else if self.get_current_username().is_none() {
+                    self.display_error(sender, "No username entered", "No username to log in with");
+                } else {
+                    self.display_error(
+                        sender,
+                        "Invalid session command",
+                        "Can't log in with an invalid session command",
+                    );
+                }
             }
         };
     }
 
     /// Send the entered input for logging in.
-    async fn send_input(&mut self, sender: &AsyncComponentSender<Self>, input: String) {
+    fn send_input(&mut self, sender: &AsyncComponentSender<Self>, input: String) {
         // Reset the password field, for convenience when the user has to re-enter a password.
         self.updates.set_input(String::new());
 
         // Send the password, as authentication for the current user.
-        let resp = self
-            .greetd_client
-            .lock()
-            .await
-            .send_auth_response(Some(input))
-            .await
-            .unwrap_or_else(|err| panic!("Failed to send input: {err}"));
+        let client = Arc::clone(&self.greetd_client);
+        self.call_greetd(sender, async move {
+            let response = client
+                .lock()
+                .await
+                .send_auth_response(Some(input))
+                .await
+                .unwrap_or_else(|err| panic!("Failed to send input: {err}"));
+            CommandMsg::HandleGreetdResponse(response)
+        });
+    }
+
+    /// Show the avatar of the currently selected user, or their initials if there's no avatar.
+    fn update_avatar(&mut self) {
+        let username = if let Some(username) = self.get_current_username() {
+            username
+        } else {
+            return;
+        };
+
+        // Users are mapped from full name to username, so search for the full name.
+        let full_name = self
+            .sys_util
+            .get_users()
+            .iter()
+            .find(|(_, name)| **name == username)
+            .map_or(username.as_str(), |(full_name, _)| full_name.as_str());
 
-        self.handle_greetd_response(sender, resp).await;
+        self.updates.set_initials(get_initials(full_name));
+        self.updates.set_avatar_color(get_avatar_color(&username));
+        self.updates
+            .set_avatar(self.sys_util.get_avatars().get(&username).cloned());
+    }
+
+    /// Show the name, icon and description of the session chosen in the dropdown.
+    fn update_session_display(&mut self) {
+        let info = self.sess_info.as_ref().expect("No session info set yet");
+        let name = info.sess_id.as_ref().map_or("", GString::as_str);
+        let session = self.sys_util.get_sessions().get(name);
+        self.updates.set_sess_name(name.to_string());
+        self.updates
+            .set_sess_icon(session.and_then(|session| session.icon.clone()));
+        self.updates
+            .set_sess_comment(session.and_then(|session| session.comment.clone()));
     }
 
     /// Get the currently selected username.
     fn get_current_username(&self) -> Option<String> {
         let info = self.sess_info.as_ref().expect("No session info set yet");
-        Some(info.user_text.to_string())
+        if self.updates.manual_user_mode {
+            debug!(
+                "Retrieved username '{}' through manual entry",
+                info.user_text
+            );
+            Some(info.user_text.to_string()).filter(|username| !username.is_empty())
+        } else if let Some(username) = &info.user_id {
+            // Get the currently selected user's ID, which should be their username.
+            debug!("Retrieved username '{username}' from options");
+            Some(username.to_string())
+        } else {
+            None
+        }
     }
 
     /// Get the currently selected session name (if available) and command.
+    ///
+    /// The name is only available for sessions chosen from the dropdown, and not for manually
+    /// entered commands.
     fn get_current_session_cmd(
         &mut self,
         sender: &AsyncComponentSender<Self>,
     ) -> (Option<String>, Option<Vec<String>>) {
         let info = self.sess_info.as_ref().expect("No session info set yet");
-        debug!(
-            "Retrieved session command '{}' through manual entry",
-            info.sess_text
-        );
-        if let Some(cmd) = shlex::split(info.sess_text.as_str()) {
-            (None, Some(cmd))
-        } else {
-            // This must be an invalid command.
-            self.display_error(
-                sender,
-                "Invalid session command",
-                &format!("Invalid session command: {}", info.sess_text),
+        if self.updates.manual_sess_mode {
+            debug!(
+                "Retrieved session command '{}' through manual entry",
+                info.sess_text
             );
+            if let Some(cmd) = shlex::split(info.sess_text.as_str()) {
+                (None, Some(cmd))
+            } else {
+                // This must be an invalid command.
+                self.display_error(
+                    sender,
+                    "Invalid session command",
+                    &format!("Invalid session command: {}", info.sess_text),
+                );
+                (None, None)
+            }
+        } else if let Some(session) = &info.sess_id {
+            // Get the currently selected session's ID, which should be its name.
+            if let Some(session_info) = self.sys_util.get_sessions().get(session.as_str()) {
+                debug!(
+                    "Retrieved command '{:?}' for session '{session}' from options",
+                    session_info.command
+                );
+                (
+                    Some(session.to_string()),
+                    Some(session_info.command.clone()),
+                )
+            } else {
+                // The session was probably removed after it was chosen.
+                self.display_error(
+                    sender,
+                    "Session not found",
+                    &format!("Session '{session}' not found"),
+                );
+                (None, None)
+            }
+        } else {
+            self.display_error(sender, "No session selected", "No session selected");
             (None, None)
         }
     }
 
     /// Start the session for the selected user.
-    async fn start_session(&mut self, sender: &AsyncComponentSender<Self>) {
+    fn start_session(&mut self, sender: &AsyncComponentSender<Self>) {
         // Get the session command.
         let (session, cmd) = if let (session, Some(cmd)) = self.get_current_session_cmd(sender) {
             (session, cmd)
@@ -496,13 +1499,25 @@ impl Greeter {
         }
 
         // Start the session.
-        let response = self
-            .greetd_client
-            .lock()
-            .await
-            .start_session(cmd, environment)
-            .await
-            .unwrap_or_else(|err| panic!("Failed to start session: {err}"));
+        let client = Arc::clone(&self.greetd_client);
+        self.call_greetd(sender, async move {
+            let response = client
+                .lock()
+                .await
+                .start_session(cmd, environment)
+                .await
+                .unwrap_or_else(|err| panic!("Failed to start session: {err}"));
+            CommandMsg::HandleStartSession(response)
+        });
+    }
+
+    /// Event handler for the response from greetd after starting the session
+    pub(super) fn start_session_handler(
+        &mut self,
+        sender: &AsyncComponentSender<Self>,
+        response: Response,
+    ) {
+        self.updates.set_busy(false);
 
         match response {
             Response::Success => {
@@ -513,7 +1528,7 @@ impl Greeter {
             Response::AuthMessage { .. } => unimplemented!(),
 
             Response::Error { description, .. } => {
-                self.cancel_click_handler().await;
+                self.cancel_session(sender);
                 self.display_error(
                     sender,
                     "Failed to start session",
@@ -5,7 +5,10 @@
 //! Templates for various GUI components
 
 use gtk::prelude::*;
-use relm4::{gtk, RelmWidgetExt, WidgetTemplate};
+use relm4::{gtk, WidgetTemplate};
+
+/// Size (in pixels) of a session's icon
+pub const SESSION_ICON_SIZE: i32 = 32;
 
 /// Button that ends the greeter (eg. Reboot)
 #[relm4::widget_template(pub)]
@@ -13,16 +16,7 @@ impl WidgetTemplate for EndButton {
     view! {
         gtk::Button {
             set_focusable: true,
-            inline_css: "
-            padding: 16px;
-            border-radius: 50px;
-            background-image: none;
-            background-color: #6e7acc;
-            color: white;
-            outline-color: #515c88;
-            border: none;
-            font-size: 24px;
-            ",
+            add_css_class: "end-button",
         }
     }
 }
@@ -32,7 +26,7 @@ impl WidgetTemplate for EndButton {
 impl WidgetTemplate for EntryLabel {
     view! {
         gtk::Label {
-            set_width_request: 100,
+            add_css_class: "entry-label",
             set_xalign: 1.0,
         }
     }
@@ -45,129 +39,286 @@ impl WidgetTemplate for Ui {
         gtk::Overlay {
             /// Background image
             #[name = "background"]
-            gtk::Picture,
+            gtk::Picture {
+                add_css_class: "background",
+            },
 
             /// Main login box
+            #[name = "login_panel"]
             add_overlay = &gtk::Frame {
+                set_widget_name: "login-panel",
                 set_halign: gtk::Align::Center,
                 set_valign: gtk::Align::End,
-                set_margin_bottom: 120,
-                inline_css: "background-color: transparent; border: none;",
 
+                #[name = "login_box"]
                 gtk::Box {
+                    set_widget_name: "login-box",
                     set_orientation: gtk::Orientation::Vertical,
-                    set_margin_bottom: 15,
-                    set_margin_end: 15,
-                    set_margin_start: 15,
-                    set_margin_top: 15,
-
-                    /// Widget where the user enters a secret
-                    #[name = "secret_entry"]
-                    gtk::PasswordEntry {
-                        set_show_peek_icon: true,
-                        set_width_request: 300,
-                        set_height_request: 50,
+                    // Keep the contents centered when the panel fills the screen's height.
+                    set_valign: gtk::Align::Center,
+
+                    /// Message to the user, such as the greeting or info from greetd
+                    #[name = "message_label"]
+                    gtk::Label {
+                        set_widget_name: "message-label",
                         set_halign: gtk::Align::Center,
-                        inline_css: "
-                        outline-color: #515c88;
-                        border-radius: 25px;
-                        background-color: rgba(229, 215, 230, 1);
-                        padding: 0px 25px 0px 25px;
-                        font-size: 16px;
-                        ",
-                     },
-                },
-            },
+                        set_justify: gtk::Justification::Center,
+                        set_wrap: true,
+                    },
 
-            add_overlay = &gtk::Frame {
-                set_halign: gtk::Align::Center,
-                set_valign: gtk::Align::End,
-                set_margin_bottom: 200,
-                inline_css: "background-color: transparent; border: none;",
+                    /// Warning shown before the greeter runs a power action because it's idle
+                    #[name = "idle_warning_label"]
+                    gtk::Label {
+                        set_widget_name: "idle-warning-label",
+                        set_halign: gtk::Align::Center,
+                        set_justify: gtk::Justification::Center,
+                        set_visible: false,
+                        set_wrap: true,
+                    },
 
-                /// Notification bar for error messages
-                #[name = "error_info"]
-                gtk::Box {
-                    // During init, the info bar closing animation is shown. To hide that, make
-                    // it invisible. Later, the code will permanently make it visible, so that
-                    // `InfoBar::set_revealed` will work properly with animations.
-                    set_visible: false,
+                    /// Circular frame for the avatar of the current user
+                    gtk::Frame {
+                        set_widget_name: "avatar-frame",
+                        set_halign: gtk::Align::Center,
+                        set_overflow: gtk::Overflow::Hidden,
+
+                        gtk::Box {
+                            /// Avatar image of the current user
+                            #[name = "avatar_image"]
+                            gtk::Image {
+                                set_widget_name: "avatar-image",
+                            },
+
+                            /// Initials of the current user, shown when there's no avatar image
+                            #[name = "avatar_initials"]
+                            gtk::Label {
+                                set_widget_name: "avatar-initials",
+                            },
+                        },
+                    },
+
+                    /// Widgets to choose the user
+                    #[name = "user_chooser"]
+                    gtk::Box {
+                        set_widget_name: "user-chooser",
+                        set_halign: gtk::Align::Center,
+
+                        /// Widget to display the users
+                        #[name = "usernames_box"]
+                        gtk::ComboBoxText {
+                            set_widget_name: "usernames-box",
+                        },
+
+                        /// Widget where the user enters the username
+                        #[name = "username_entry"]
+                        gtk::Entry {
+                            set_widget_name: "username-entry",
+                            set_placeholder_text: Some("Username"),
+                            set_visible: false,
+                        },
+
+                        /// Toggle manual entry of the username
+                        #[name = "user_toggle"]
+                        gtk::ToggleButton {
+                            set_widget_name: "user-toggle",
+                            set_icon_name: "document-edit-symbolic",
+                            set_tooltip_text: Some("Manually enter username"),
+                        },
+                    },
+
+                    /// Widgets to choose the session, along with its description
+                    #[name = "session_details"]
+                    gtk::Box {
+                        set_widget_name: "session-details",
+                        set_orientation: gtk::Orientation::Vertical,
+
+                        /// Widgets to choose the session
+                        gtk::Box {
+                            set_widget_name: "session-chooser",
+                            set_halign: gtk::Align::Center,
+
+                            /// Icon of the current session
+                            #[name = "session_icon"]
+                            gtk::Image {
+                                set_widget_name: "session-icon",
+                                set_pixel_size: SESSION_ICON_SIZE,
+                            },
+
+                            /// Button showing the current session, which opens the list of sessions
+                            #[name = "sessions_button"]
+                            gtk::MenuButton {
+                                set_widget_name: "sessions-button",
+
+                                #[wrap(Some)]
+                                set_popover = &gtk::Popover {
+                                    #[wrap(Some)]
+                                    set_child = &gtk::ScrolledWindow {
+                                        set_hscrollbar_policy: gtk::PolicyType::Never,
+                                        set_max_content_height: 400,
+                                        set_propagate_natural_height: true,
 
-                    /// The actual error message
-                    #[name = "error_label"]
+                                        /// Widget to display the sessions
+                                        #[name = "sessions_list"]
+                                        gtk::ListBox {
+                                            set_widget_name: "sessions-list",
+                                            set_selection_mode: gtk::SelectionMode::None,
+                                        },
+                                    },
+                                },
+                            },
+
+                            /// Widget where the user enters the session
+                            #[name = "session_entry"]
+                            gtk::Entry {
+                                set_widget_name: "session-entry",
+                                set_placeholder_text: Some("Session command"),
+                                set_visible: false,
+                            },
+
+                            /// Toggle manual entry of the session
+                            #[name = "sess_toggle"]
+                            gtk::ToggleButton {
+                                set_widget_name: "session-toggle",
+                                set_icon_name: "document-edit-symbolic",
+                                set_tooltip_text: Some("Manually enter session command"),
+                            },
+                        },
+
+                        /// Description of the current session
+                        #[name = "session_comment"]
+                        gtk::Label {
+                            set_widget_name: "session-comment",
+                        },
+                    },
+
+                    /// Prompt sent by greetd for the input
+                    #[name = "input_prompt_label"]
                     gtk::Label {
+                        set_widget_name: "input-prompt-label",
+                        set_halign: gtk::Align::Center,
+                    },
+
+                    /// Widgets where the user enters input
+                    gtk::Box {
+                        set_widget_name: "input-box",
+                        set_halign: gtk::Align::Center,
+
+                        /// Widget where the user enters a secret
+                        #[name = "secret_entry"]
+                        gtk::PasswordEntry {
+                            set_widget_name: "secret-entry",
+                            add_css_class: "input-entry",
+                            set_show_peek_icon: true,
+                            set_halign: gtk::Align::Center,
+                        },
+
+                        /// Widget where the user enters input that need not be hidden
+                        #[name = "visible_entry"]
+                        gtk::Entry {
+                            set_widget_name: "visible-entry",
+                            add_css_class: "input-entry",
+                            set_halign: gtk::Align::Center,
+                            set_visible: false,
+                        },
+
+                        /// Button to cancel the login attempt
+                        #[name = "cancel_button"]
+                        gtk::Button {
+                            set_widget_name: "cancel-button",
+                            set_icon_name: "go-previous-symbolic",
+                            set_tooltip_text: Some("Cancel"),
+                            set_valign: gtk::Align::Center,
+                        },
+
+                        /// Spinner shown while waiting for greetd
+                        #[name = "busy_spinner"]
+                        gtk::Spinner {
+                            set_widget_name: "busy-spinner",
+                            set_valign: gtk::Align::Center,
+                            set_visible: false,
+                        },
+                    },
+
+                    /// Notification bar for error messages
+                    #[name = "error_info"]
+                    gtk::Box {
+                        set_widget_name: "error-box",
                         set_halign: gtk::Align::Center,
-                        inline_css: "
-                        color: white;
-                        background-color: #6e7acc;
-                        border-radius: 40px;
-                        font-size: 16px;
-                        padding: 12px;
-                        ",
+                        // During init, the info bar closing animation is shown. To hide that, make
+                        // it invisible. Later, the code will permanently make it visible, so that
+                        // `InfoBar::set_revealed` will work properly with animations.
+                        set_visible: false,
+
+                        /// The actual error message
+                        #[name = "error_label"]
+                        gtk::Label {
+                            set_widget_name: "error-label",
+                            set_halign: gtk::Align::Center,
+                        },
                     },
                 },
             },
 
+            /// Panel showing the issue file (eg. `/etc/issue`)
+            #[name = "issue_panel"]
+            add_overlay = &gtk::Frame {
+                set_widget_name: "issue-panel",
+                set_halign: gtk::Align::Start,
+                set_valign: gtk::Align::Start,
+                set_visible: false,
+
+                #[name = "issue_label"]
+                gtk::Label {
+                    set_widget_name: "issue-label",
+                    set_selectable: false,
+                    set_wrap: true,
+                    set_xalign: 0.0,
+                },
+            },
+
             /// Clock widget
+            #[name = "clock_box"]
             add_overlay = &gtk::Box {
+                set_widget_name: "clock",
                 set_orientation: gtk::Orientation::Vertical,
                 set_halign: gtk::Align::Center,
                 set_valign: gtk::Align::Start,
-                set_margin_top: 80,
-                // Make it fit cleanly onto the top edge of the screen.
-                inline_css: "
-                    border: none;
-                    color: #6e7acc;
-                    background-color: transparent;
-                ",
 
                 /// Label displaying the current date & time
                 #[name = "date_label"]
                 gtk::Label {
+                    set_widget_name: "date-label",
                     set_use_markup: true,
-                    inline_css: "
-                    font-size: 34px;
-                    font-family: JetbrainsMono Nerd Font;
-                    "
                  },
                 /// Label displaying the current date & time
                 #[name = "time_label"]
                 gtk::Label {
+                    set_widget_name: "time-label",
                     set_use_markup: true,
-                    inline_css: "
-                    font-size: 94px;
-                    font-family: JetbrainsMono Nerd Font;
-                    "
+                 },
+                /// Label displaying the current time in other time zones
+                #[name = "timezones_label"]
+                gtk::Label {
+                    set_widget_name: "timezones-label",
+                    set_justify: gtk::Justification::Center,
+                    set_visible: false,
                  },
             },
 
             /// Collection of widgets appearing at the bottom
+            #[name = "bottom_box"]
             add_overlay = &gtk::Box {
+                set_widget_name: "bottom-box",
                 set_orientation: gtk::Orientation::Vertical,
                 set_halign: gtk::Align::Center,
                 set_valign: gtk::Align::End,
-                set_margin_bottom: 15,
-                set_spacing: 15,
 
-                /// Collection of buttons that close the greeter (eg. Reboot)
+                /// Collection of buttons that close the greeter (eg. Reboot), added from the config
+                #[name = "power_actions_box"]
                 gtk::Box {
+                    set_widget_name: "power-actions-box",
                     set_halign: gtk::Align::Center,
                     set_homogeneous: true,
-                    set_spacing: 15,
-
-                    /// Button to reboot
-                    #[name = "reboot_button"]
-                    #[template]
-                    EndButton {
-                         set_label: "Reboot",
-                    },
-
-                    /// Button to power-off
-                    #[name = "poweroff_button"]
-                    #[template]
-                    EndButton {
-                        set_label: "Power Off",
-                 },
                 },
             },
         }
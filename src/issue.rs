@@ -0,0 +1,209 @@
+// SPDX-FileCopyrightText: 2022 Harish Rajagopal <harish.rajagopals@gmail.com>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Rendering of issue files (eg. `/etc/issue`), as done by agetty
+
+use std::collections::HashMap;
+use std::env::consts::ARCH;
+use std::fs::read;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+
+use chrono::Local;
+use tracing::warn;
+
+use crate::sysutil::SysUtil;
+
+/// Format of the date inserted by `\d`
+const DATE_FMT: &str = "%a %b %e %Y";
+/// Format of the time inserted by `\t`
+const TIME_FMT: &str = "%H:%M:%S";
+
+/// Info about the system that escapes in an issue file expand to
+pub struct IssueInfo {
+    /// Hostname, inserted by `\n`
+    pub hostname: String,
+    /// Release of the kernel, inserted by `\r`
+    pub kernel_release: String,
+    /// Name of the kernel, inserted by `\s`
+    pub kernel_name: String,
+    /// Version of the kernel, inserted by `\v`
+    pub kernel_version: String,
+    /// Architecture of the machine, inserted by `\m`
+    pub machine: String,
+    /// Current date, inserted by `\d`
+    pub date: String,
+    /// Current time, inserted by `\t`
+    pub time: String,
+    /// Fields in `os-release`, inserted by `\S` or `\S{FIELD}`
+    pub os_release: HashMap<String, String>,
+}
+
+impl IssueInfo {
+    /// Get the info about this system.
+    pub fn new() -> Self {
+        let (kernel_release, kernel_name, kernel_version) = SysUtil::get_kernel_info();
+        let now = Local::now();
+        Self {
+            hostname: SysUtil::get_hostname().unwrap_or_default(),
+            kernel_release,
+            kernel_name,
+            kernel_version,
+            machine: ARCH.to_string(),
+            date: now.format(DATE_FMT).to_string(),
+            time: now.format(TIME_FMT).to_string(),
+            os_release: SysUtil::get_os_release(),
+        }
+    }
+
+    /// Update the current date and time, since they change while the issue file is shown.
+    pub fn update_time(&mut self) {
+        let now = Local::now();
+        self.date = now.format(DATE_FMT).to_string();
+        self.time = now.format(TIME_FMT).to_string();
+    }
+}
+
+/// Read an issue file, without expanding the escapes in it.
+pub fn load_issue(path: &Path) -> Option<String> {
+    match read(path) {
+        Ok(contents) => Some(String::from_utf8_lossy(&contents).into_owned()),
+        Err(err) => {
+            warn!("Couldn't read issue file '{}': {err}", path.display());
+            None
+        }
+    }
+}
+
+/// Take the argument in braces following an escape, such as `NAME` in `\S{NAME}`.
+fn take_arg(chars: &mut Peekable<Chars>) -> Option<String> {
+    if chars.peek() == Some(&'{') {
+        chars.next();
+        Some(chars.take_while(|&chr| chr != '}').collect())
+    } else {
+        None
+    }
+}
+
+/// Expand the agetty-style escapes in the text of an issue file.
+///
+/// Escapes that only make sense on a terminal (eg. `\l` for the TTY) are removed. Unknown escapes
+/// are kept as they are.
+pub fn expand_escapes(text: &str, info: &IssueInfo) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(chr) = chars.next() {
+        if chr != '\\' {
+            expanded.push(chr);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => expanded.push_str(&info.hostname),
+            Some('r') => expanded.push_str(&info.kernel_release),
+            Some('s') => expanded.push_str(&info.kernel_name),
+            Some('v') => expanded.push_str(&info.kernel_version),
+            Some('m') => expanded.push_str(&info.machine),
+            Some('d') => expanded.push_str(&info.date),
+            Some('t') => expanded.push_str(&info.time),
+            Some('S') => {
+                let value = if let Some(field) = take_arg(&mut chars) {
+                    info.os_release.get(&field)
+                } else {
+                    // Like agetty, fall back to the kernel name without a pretty name.
+                    Some(
+                        info.os_release
+                            .get("PRETTY_NAME")
+                            .unwrap_or(&info.kernel_name),
+                    )
+                };
+                expanded.push_str(value.map_or("", String::as_str));
+            }
+            Some('l' | 'b' | 'u' | 'U' | 'e' | '4' | '6' | 'o' | 'O') => {
+                take_arg(&mut chars);
+            }
+            Some('\\') => expanded.push('\\'),
+            Some(other) => {
+                expanded.push('\\');
+                expanded.push(other);
+            }
+            None => expanded.push('\\'),
+        }
+    }
+
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_info() -> IssueInfo {
+        IssueInfo {
+            hostname: "lab-pc-07".to_string(),
+            kernel_release: "6.1.8-arch1-1".to_string(),
+            kernel_name: "Linux".to_string(),
+            kernel_version: "#1 SMP PREEMPT_DYNAMIC".to_string(),
+            machine: "x86_64".to_string(),
+            date: "Sun Oct 19 2026".to_string(),
+            time: "09:41:00".to_string(),
+            os_release: [
+                ("NAME", "Arch Linux"),
+                ("PRETTY_NAME", "Arch Linux (Rolling)"),
+                ("ID", "arch"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn text_without_escapes_is_unchanged() {
+        let text = "Authorized use only.\nAll activity is logged.\n";
+        assert_eq!(expand_escapes(text, &test_info()), text);
+    }
+
+    #[test]
+    fn system_info_escapes_are_expanded() {
+        assert_eq!(
+            expand_escapes(r"\S on \n (\s \r, \m)", &test_info()),
+            "Arch Linux (Rolling) on lab-pc-07 (Linux 6.1.8-arch1-1, x86_64)"
+        );
+        assert_eq!(
+            expand_escapes(r"\d \t", &test_info()),
+            "Sun Oct 19 2026 09:41:00"
+        );
+    }
+
+    #[test]
+    fn os_release_fields_are_expanded() {
+        assert_eq!(
+            expand_escapes(r"\S{NAME} [\S{ID}] [\S{MISSING}]", &test_info()),
+            "Arch Linux [arch] []"
+        );
+    }
+
+    #[test]
+    fn pretty_name_falls_back_to_kernel_name() {
+        let mut info = test_info();
+        info.os_release.clear();
+        assert_eq!(expand_escapes(r"Welcome to \S", &info), "Welcome to Linux");
+    }
+
+    #[test]
+    fn terminal_escapes_are_removed() {
+        assert_eq!(
+            expand_escapes(r"\S \r (\l)\e{reset} \4{eth0}", &test_info()),
+            "Arch Linux (Rolling) 6.1.8-arch1-1 () "
+        );
+    }
+
+    #[test]
+    fn backslashes_and_unknown_escapes_are_kept() {
+        assert_eq!(expand_escapes(r"C:\\ \q \", &test_info()), r"C:\ \q \");
+    }
+}
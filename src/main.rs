@@ -7,6 +7,7 @@ mod client;
 mod config;
 mod constants;
 mod gui;
+mod issue;
 mod sysutil;
 mod tomlutils;
 
@@ -4,52 +4,134 @@
 
 //! Helper for system utilities like users and sessions
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env;
-use std::fs::read;
+use std::fs::{metadata, read, read_dir};
 use std::io::Result as IOResult;
-use std::path::Path;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 use std::str::from_utf8;
+use std::time::SystemTime;
 
-use glob::glob;
+use glob::{glob, Pattern};
 use pwd::Passwd;
 use regex::Regex;
 use tracing::{debug, info, warn};
 
+use crate::config::{SessionSettings, UserSettings};
 use crate::constants::SESSION_DIRS;
 
 /// Path to the file that contains min/max UID of a regular user
 pub const LOGIN_FILE: &str = "/etc/login.defs";
+/// Path to the file that contains the list of users
+const PASSWD_FILE: &str = "/etc/passwd";
+/// Path to the file that contains the list of groups
+const GROUP_FILE: &str = "/etc/group";
 /// Default minimum UID for `useradd` (a/c to my system)
 const DEFAULT_UID_MIN: u32 = 1000;
 /// Default maximum UID for `useradd` (a/c to my system)
 const DEFAULT_UID_MAX: u32 = 60000;
+/// Directory where AccountsService stores per-user settings and avatars
+const ACCOUNTS_SERVICE_DIR: &str = "/var/lib/AccountsService";
+/// Avatar files that can be placed in a user's home directory, in order of preference
+const HOME_AVATAR_FILES: [&str; 2] = [".face", ".face.icon"];
+/// Files containing the hostname, in order of preference
+const HOSTNAME_FILES: [&str; 2] = ["/proc/sys/kernel/hostname", "/etc/hostname"];
+/// File containing the release of the kernel
+const KERNEL_RELEASE_FILE: &str = "/proc/sys/kernel/osrelease";
+/// File containing the name of the kernel
+const KERNEL_NAME_FILE: &str = "/proc/sys/kernel/ostype";
+/// File containing the version of the kernel
+const KERNEL_VERSION_FILE: &str = "/proc/sys/kernel/version";
+/// Files containing info about the OS, in order of preference
+const OS_RELEASE_FILES: [&str; 2] = ["/etc/os-release", "/usr/lib/os-release"];
+/// Extensions of image files used as backgrounds, when searching directories
+const BACKGROUND_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "webp", "svg", "gif", "bmp"];
+/// Directories searched for commands when `PATH` isn't set
+const DEFAULT_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
 /// XDG data directory variable name (parent directory for X11/Wayland sessions)
 const XDG_DIR_ENV_VAR: &str = "XDG_DATA_DIRS";
 
 // Convenient aliases for used maps
 type UserMap = HashMap<String, String>;
-type ShellMap = HashMap<String, Vec<String>>;
-type SessionMap = HashMap<String, Vec<String>>;
+type AvatarMap = HashMap<String, PathBuf>;
+type SessionMap = HashMap<String, SessionInfo>;
+
+/// Modification times of the files and directories that the users and sessions are read from
+pub type SourceMtimes = Vec<(PathBuf, Option<SystemTime>)>;
+
+/// Info about an X11/Wayland session, taken from its desktop file
+#[derive(Clone)]
+pub struct SessionInfo {
+    /// The command that starts the session
+    pub command: Vec<String>,
+    /// Icon name or absolute path to the icon image
+    pub icon: Option<String>,
+    /// Short description of the session
+    pub comment: Option<String>,
+}
+
+/// A boot entry from the Boot Loader Specification, which the system can be rebooted into
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BootEntry {
+    /// The ID of the entry, which is the name of its file (eg. `arch.conf`), as used by systemd
+    pub id: String,
+    /// Human-readable name of the entry
+    pub title: String,
+    /// Key that orders the entry among other entries
+    sort_key: Option<String>,
+    /// Version of the entry, which orders entries with the same sort key
+    version: Option<String>,
+}
+
+/// Compare two version strings, such that numbers in them are compared by value.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    /// Split the version into runs of digits and non-digits.
+    fn split(version: &str) -> Vec<&str> {
+        let mut parts = Vec::new();
+        let mut start = 0;
+        for (i, chr) in version.char_indices().skip(1) {
+            let prev_is_digit = version[..i].ends_with(|prev: char| prev.is_ascii_digit());
+            if prev_is_digit != chr.is_ascii_digit() {
+                parts.push(&version[start..i]);
+                start = i;
+            }
+        }
+        parts.push(&version[start..]);
+        parts
+    }
+
+    for (part_a, part_b) in split(a).into_iter().zip(split(b)) {
+        let ordering = match (part_a.parse::<u64>(), part_b.parse::<u64>()) {
+            (Ok(num_a), Ok(num_b)) => num_a.cmp(&num_b),
+            _ => part_a.cmp(part_b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
 
 /// Stores info of all regular users and sessions
 pub struct SysUtil {
     /// Maps a user's full name to their system username
     users: UserMap,
-    /// Maps a system username to their shell
-    shells: ShellMap,
-    /// Maps a session's full name to its command
+    /// Maps a system username to the path of their avatar image
+    avatars: AvatarMap,
+    /// Maps a session's full name to its info
     sessions: SessionMap,
 }
 
 impl SysUtil {
-    pub fn new() -> IOResult<Self> {
-        let (users, shells) = Self::init_users()?;
+    pub fn new(user_settings: &UserSettings, sess_settings: &SessionSettings) -> IOResult<Self> {
+        let (users, avatars) = Self::init_users(user_settings)?;
         Ok(Self {
             users,
-            shells,
-            sessions: Self::init_sessions()?,
+            avatars,
+            sessions: Self::init_sessions(sess_settings)?,
         })
     }
 
@@ -95,13 +177,14 @@ impl SysUtil {
 
     /// Get the list of regular users.
     ///
-    /// These are defined as a list of users with UID between `UID_MIN` and `UID_MAX`.
-    fn init_users() -> IOResult<(UserMap, ShellMap)> {
+    /// These are defined as a list of users with UID between `UID_MIN` and `UID_MAX`, excluding
+    /// those marked as system accounts by AccountsService.
+    fn init_users(settings: &UserSettings) -> IOResult<(UserMap, AvatarMap)> {
         let (min_uid, max_uid) = Self::get_uid_limits()?;
         debug!("UID_MIN: {min_uid}, UID_MAX: {max_uid}");
 
         let mut users = HashMap::new();
-        let mut shells = HashMap::new();
+        let mut avatars = HashMap::new();
 
         // Iterate over all users in /etc/passwd.
         for entry in Passwd::iter() {
@@ -110,6 +193,21 @@ impl SysUtil {
                 continue;
             };
 
+            let account_info = AccountsServiceInfo::load(&entry.name);
+            if account_info.system_account {
+                debug!("Skipping system account: {}", entry.name);
+                continue;
+            };
+
+            if let Some(avatar) = Self::find_avatar(&entry, &account_info, settings) {
+                debug!(
+                    "Found avatar for user '{}': {}",
+                    entry.name,
+                    avatar.display()
+                );
+                avatars.insert(entry.name.clone(), avatar);
+            };
+
             // Use the actual system username if the "full name" is not available.
             let full_name = if let Some(gecos) = entry.gecos {
                 if gecos.is_empty() {
@@ -134,45 +232,86 @@ impl SysUtil {
                 );
                 entry.name.clone()
             };
-            users.insert(full_name, entry.name.clone());
+            users.insert(full_name, entry.name);
+        }
 
-            if let Some(cmd) = shlex::split(entry.shell.as_str()) {
-                shells.insert(entry.name, cmd);
-            } else {
-                // Skip this user, since a missing command means that we can't use it.
-                warn!(
-                    "Couldn't split shell of username '{}' into arguments: {}",
-                    entry.name, entry.shell
-                );
-            };
+        Ok((users, avatars))
+    }
+
+    /// Find the avatar image for a user.
+    ///
+    /// These are searched for in the following order:
+    /// 1. The avatar directory in the config, as `<dir>/<username>` or `<dir>/<username>.*`
+    /// 2. The `Icon` set in the AccountsService user file
+    /// 3. The AccountsService icon directory
+    /// 4. `~/.face` and `~/.face.icon`
+    fn find_avatar(
+        entry: &Passwd,
+        account_info: &AccountsServiceInfo,
+        settings: &UserSettings,
+    ) -> Option<PathBuf> {
+        let mut candidates = Vec::new();
+
+        if let Some(avatar_dir) = &settings.avatar_dir {
+            candidates.push(Path::new(avatar_dir).join(&entry.name));
+            let pattern = format!("{avatar_dir}/{}.*", Pattern::escape(&entry.name));
+            if let Ok(glob_paths) = glob(&pattern) {
+                candidates.extend(glob_paths.filter_map(Result::ok));
+            }
+        };
+
+        if let Some(icon) = &account_info.icon {
+            candidates.push(icon.clone());
+        };
+        candidates.push(
+            Path::new(ACCOUNTS_SERVICE_DIR)
+                .join("icons")
+                .join(&entry.name),
+        );
+
+        // The home directory may not be readable by the greeter, in which case these are skipped.
+        for file in HOME_AVATAR_FILES {
+            candidates.push(Path::new(&entry.dir).join(file));
         }
 
-        Ok((users, shells))
+        candidates.into_iter().find(|path| path.is_file())
     }
 
     /// Get available X11 and Wayland sessions.
     ///
     /// These are defined as either X11 or Wayland session desktop files stored in specific
-    /// directories.
-    fn init_sessions() -> IOResult<SessionMap> {
+    /// directories. Desktop files whose paths match any of the exclusion patterns are skipped.
+    fn init_sessions(settings: &SessionSettings) -> IOResult<SessionMap> {
         let mut found_session_names = HashSet::new();
         let mut sessions = HashMap::new();
 
-        // Use the XDG spec if available, else use the one that's compiled.
-        // The XDG env var can change after compilation in some distros like NixOS.
-        let session_dirs = if let Ok(sess_parent_dirs) = env::var(XDG_DIR_ENV_VAR) {
-            debug!("Found XDG env var {XDG_DIR_ENV_VAR}: {sess_parent_dirs}");
-            match sess_parent_dirs
-                .split(':')
-                .map(|parent_dir| format!("{parent_dir}/xsessions:{parent_dir}/wayland-sessions"))
-                .reduce(|a, b| a + ":" + &b)
-            {
-                None => SESSION_DIRS.to_string(),
-                Some(dirs) => dirs,
-            }
-        } else {
-            SESSION_DIRS.to_string()
-        };
+        let exclude_patterns: Vec<Pattern> = settings
+            .exclude
+            .iter()
+            .filter_map(|pattern| match Pattern::new(pattern) {
+                Ok(pattern) => Some(pattern),
+                Err(err) => {
+                    warn!("Invalid session exclusion pattern '{pattern}': {err}");
+                    None
+                }
+            })
+            .collect();
+
+        // The session launch command is specified as: Exec=command arg1 arg2...
+        let cmd_regex = Regex::new(r"Exec=(.*)").expect("Invalid regex for session command");
+        // The session name is specified as: Name=My Session
+        let name_regex = Regex::new(r"Name=(.*)").expect("Invalid regex for session name");
+        // The session icon and description are specified as: Icon=sway, Comment=...
+        let icon_regex = Regex::new(r"(?m)^Icon=(.*)$").expect("Invalid regex for session icon");
+        let comment_regex =
+            Regex::new(r"(?m)^Comment=(.*)$").expect("Invalid regex for session comment");
+
+        // Hiding could be either as Hidden=true or NoDisplay=true
+        let hidden_regex = Regex::new(r"Hidden=(.*)").expect("Invalid regex for hidden");
+        let no_display_regex = Regex::new(r"NoDisplay=(.*)").expect("Invalid regex for no display");
+
+        let session_dirs = Self::get_session_dirs(settings);
+        debug!("Using session directories: {session_dirs}");
 
         for sess_dir in session_dirs.split(':') {
             let sess_parent_dir = if let Some(sess_parent_dir) = Path::new(sess_dir).parent() {
@@ -193,6 +332,13 @@ impl SysUtil {
                         continue;
                     }
                 };
+                if exclude_patterns
+                    .iter()
+                    .any(|pattern| pattern.matches_path(&path))
+                {
+                    info!("Skipping excluded session file: {}", path.display());
+                    continue;
+                };
                 info!("Now scanning session file: {}", path.display());
 
                 let contents = read(&path)?;
@@ -216,17 +362,6 @@ impl SysUtil {
                     continue;
                 };
 
-                // The session launch command is specified as: Exec=command arg1 arg2...
-                let cmd_regex =
-                    Regex::new(r"Exec=(.*)").expect("Invalid regex for session command");
-                // The session name is specified as: Name=My Session
-                let name_regex = Regex::new(r"Name=(.*)").expect("Invalid regex for session name");
-
-                // Hiding could be either as Hidden=true or NoDisplay=true
-                let hidden_regex = Regex::new(r"Hidden=(.*)").expect("Invalid regex for hidden");
-                let no_display_regex =
-                    Regex::new(r"NoDisplay=(.*)").expect("Invalid regex for no display");
-
                 let hidden: bool = if let Some(hidden_str) = hidden_regex
                     .captures(text)
                     .and_then(|capture| capture.get(1))
@@ -304,23 +439,425 @@ impl SysUtil {
                     // session.
                     continue;
                 };
+
+                // Get the optional icon and description of this session.
+                let icon = icon_regex
+                    .captures(text)
+                    .and_then(|capture| capture.get(1))
+                    .map(|icon| icon.as_str().trim().to_string())
+                    .filter(|icon| !icon.is_empty());
+                let comment = comment_regex
+                    .captures(text)
+                    .and_then(|capture| capture.get(1))
+                    .map(|comment| comment.as_str().trim().to_string())
+                    .filter(|comment| !comment.is_empty());
+
                 found_session_names.insert(fname_and_type);
-                sessions.insert(name.to_string(), cmd);
+                sessions.insert(
+                    name.to_string(),
+                    SessionInfo {
+                        command: cmd,
+                        icon,
+                        comment,
+                    },
+                );
             }
         }
 
         Ok(sessions)
     }
 
-    /// Get the mapping of a system username to their shell.
-    pub fn get_shells(&self) -> &ShellMap {
-        &self.shells
+    /// Get the directories containing session desktop files, separated by `:`.
+    ///
+    /// The extra directories from the config come first, so that they take precedence.
+    fn get_session_dirs(settings: &SessionSettings) -> String {
+        // Use the XDG spec if available, else use the one that's compiled.
+        // The XDG env var can change after compilation in some distros like NixOS.
+        let default_dirs = if let Ok(sess_parent_dirs) = env::var(XDG_DIR_ENV_VAR) {
+            match sess_parent_dirs
+                .split(':')
+                .map(|parent_dir| format!("{parent_dir}/xsessions:{parent_dir}/wayland-sessions"))
+                .reduce(|a, b| a + ":" + &b)
+            {
+                None => SESSION_DIRS.to_string(),
+                Some(dirs) => dirs,
+            }
+        } else {
+            SESSION_DIRS.to_string()
+        };
+
+        settings
+            .extra_dirs
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(default_dirs.as_str()))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    /// Get the modification times of all files and directories that users and sessions are read
+    /// from.
+    ///
+    /// Two snapshots differ if a user, group or session was added, removed or modified in between.
+    /// Missing files have a modification time of `None`.
+    pub fn get_source_mtimes(settings: &SessionSettings) -> SourceMtimes {
+        let mut paths: Vec<PathBuf> = [PASSWD_FILE, GROUP_FILE, LOGIN_FILE]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        // AccountsService can hide users or change their avatars.
+        paths.push(Path::new(ACCOUNTS_SERVICE_DIR).join("users"));
+
+        for sess_dir in Self::get_session_dirs(settings).split(':') {
+            // The directory's mtime changes when a file is added or removed, and the files' mtimes
+            // change when they are edited.
+            paths.push(PathBuf::from(sess_dir));
+            if let Ok(glob_paths) = glob(&format!("{sess_dir}/*.desktop")) {
+                paths.extend(glob_paths.filter_map(Result::ok));
+            }
+        }
+
+        paths
+            .into_iter()
+            .map(|path| {
+                let mtime = metadata(&path).and_then(|meta| meta.modified()).ok();
+                (path, mtime)
+            })
+            .collect()
+    }
+
+    /// Get the background images at the given paths, which can be images or directories of them.
+    ///
+    /// Images in a directory are sorted by name. Missing paths are skipped with a warning.
+    pub fn get_background_images(paths: &[String]) -> Vec<PathBuf> {
+        let mut images = Vec::new();
+        for path in paths.iter().map(Path::new) {
+            if path.is_file() {
+                images.push(path.to_path_buf());
+                continue;
+            }
+            let entries = match read_dir(path) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    warn!(
+                        "Couldn't find background images in '{}': {err}",
+                        path.display()
+                    );
+                    continue;
+                }
+            };
+            let mut dir_images: Vec<_> = entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.is_file()
+                        && path.extension().map_or(false, |ext| {
+                            BACKGROUND_EXTENSIONS
+                                .iter()
+                                .any(|known| ext.eq_ignore_ascii_case(known))
+                        })
+                })
+                .collect();
+            if dir_images.is_empty() {
+                warn!("No background images in directory: {}", path.display());
+            }
+            dir_images.sort();
+            images.append(&mut dir_images);
+        }
+        debug!("Found {} background images", images.len());
+        images
+    }
+
+    /// Get the boot entries in a Boot Loader Specification entries directory (eg.
+    /// `/boot/loader/entries`).
+    ///
+    /// Entries are sorted by their `sort-key` field, with newer versions first for the same key,
+    /// followed by entries without a sort key sorted by title.
+    /// Entries without a title use their file name without the `.conf` extension as the title. A
+    /// missing directory has no entries.
+    pub fn get_boot_entries(entries_dir: &Path) -> Vec<BootEntry> {
+        let glob_paths = match glob(&format!("{}/*.conf", entries_dir.display())) {
+            Ok(glob_paths) => glob_paths,
+            Err(err) => {
+                warn!("Couldn't search for boot entries: {err}");
+                return Vec::new();
+            }
+        };
+
+        let mut entries = Vec::new();
+        for path in glob_paths.filter_map(Result::ok) {
+            let id = if let Some(name) = path.file_name() {
+                name.to_string_lossy().to_string()
+            } else {
+                continue;
+            };
+            let contents = match read(&path) {
+                Ok(contents) => contents,
+                Err(err) => {
+                    warn!("Couldn't read boot entry '{}': {err}", path.display());
+                    continue;
+                }
+            };
+            let text = String::from_utf8_lossy(&contents);
+
+            let mut title = None;
+            let mut sort_key = None;
+            let mut version = None;
+            for line in text.lines().map(str::trim) {
+                if line.starts_with('#') {
+                    continue;
+                }
+                // Keys and values are separated by whitespace.
+                let (key, value) = if let Some((key, value)) = line.split_once(char::is_whitespace)
+                {
+                    (key, value.trim())
+                } else {
+                    continue;
+                };
+                match key {
+                    "title" => title = Some(value.to_string()),
+                    "sort-key" => sort_key = Some(value.to_string()),
+                    "version" => version = Some(value.to_string()),
+                    _ => (),
+                }
+            }
+
+            debug!("Found boot entry: {id}");
+            entries.push(BootEntry {
+                title: title.unwrap_or_else(|| id.trim_end_matches(".conf").to_string()),
+                id,
+                sort_key,
+                version,
+            });
+        }
+
+        entries.sort_by(|a, b| match (&a.sort_key, &b.sort_key) {
+            (Some(key_a), Some(key_b)) => key_a.cmp(key_b).then_with(|| {
+                let version_a = a.version.as_deref().unwrap_or_default();
+                let version_b = b.version.as_deref().unwrap_or_default();
+                compare_versions(version_b, version_a)
+            }),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.title.cmp(&b.title).then_with(|| a.id.cmp(&b.id)),
+        });
+        entries
+    }
+
+    /// Get the hostname of this system.
+    pub fn get_hostname() -> Option<String> {
+        HOSTNAME_FILES.iter().find_map(|path| {
+            let contents = read(path).ok()?;
+            let hostname = String::from_utf8_lossy(&contents).trim().to_string();
+            if hostname.is_empty() {
+                None
+            } else {
+                Some(hostname)
+            }
+        })
+    }
+
+    /// Get the release (eg. "6.1.8-arch1-1"), name (eg. "Linux") and version of the kernel.
+    pub fn get_kernel_info() -> (String, String, String) {
+        let read_field = |path| {
+            read(path)
+                .map(|contents| String::from_utf8_lossy(&contents).trim().to_string())
+                .unwrap_or_else(|err| {
+                    warn!("Couldn't read '{path}': {err}");
+                    String::new()
+                })
+        };
+        (
+            read_field(KERNEL_RELEASE_FILE),
+            read_field(KERNEL_NAME_FILE),
+            read_field(KERNEL_VERSION_FILE),
+        )
     }
 
-    /// Get the mapping of a session's full name to its command.
+    /// Get the fields (eg. `NAME`) in `os-release`, which describe the OS.
+    pub fn get_os_release() -> HashMap<String, String> {
+        let path = if let Some(path) = OS_RELEASE_FILES
+            .iter()
+            .find(|path| Path::new(path).exists())
+        {
+            path
+        } else {
+            return HashMap::new();
+        };
+        let contents = match read(path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                warn!("Couldn't read '{path}': {err}");
+                return HashMap::new();
+            }
+        };
+
+        String::from_utf8_lossy(&contents)
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                // Values can be quoted like in shell scripts.
+                let value = shlex::split(value)?.join(" ");
+                Some((key.trim().to_string(), value))
+            })
+            .collect()
+    }
+
+    /// Get the name of the OS (eg. "Fedora Linux") from `os-release`.
+    pub fn get_os_name() -> Option<String> {
+        Self::get_os_release().remove("NAME")
+    }
+
+    /// Check whether a command can be run, and return the reason if it can't.
+    ///
+    /// Programs without a `/` in their name are searched for in the directories in `PATH`.
+    pub fn check_command(command: &[String]) -> Result<(), String> {
+        let program = if let Some(program) = command.first() {
+            program
+        } else {
+            return Err("No command configured".to_string());
+        };
+
+        let is_executable = |path: &Path| {
+            metadata(path)
+                .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false)
+        };
+        let found = if program.contains('/') {
+            is_executable(Path::new(program))
+        } else {
+            let path = env::var_os("PATH").unwrap_or_else(|| DEFAULT_PATH.into());
+            env::split_paths(&path).any(|dir| is_executable(&dir.join(program)))
+        };
+
+        if found {
+            Ok(())
+        } else {
+            Err(format!("Command not found: {program}"))
+        }
+    }
+
+    /// Get the mapping of a user's full name to their system username.
+    ///
+    /// If the full name is not available, the system username is used.
+    pub fn get_users(&self) -> &UserMap {
+        &self.users
+    }
+
+    /// Get the mapping of a system username to the path of their avatar image.
+    ///
+    /// Users without an avatar image are missing from this mapping.
+    pub fn get_avatars(&self) -> &AvatarMap {
+        &self.avatars
+    }
+
+    /// Get the mapping of a session's full name to its info.
     ///
     /// If the full name is not available, the filename stem is used.
     pub fn get_sessions(&self) -> &SessionMap {
         &self.sessions
     }
 }
+
+/// Info about a user from their AccountsService user file
+#[derive(Default)]
+struct AccountsServiceInfo {
+    /// Path to the user's avatar
+    icon: Option<PathBuf>,
+    /// Whether the user should be hidden from the greeter
+    system_account: bool,
+}
+
+impl AccountsServiceInfo {
+    /// Load the AccountsService user file for the given user.
+    ///
+    /// If the file can't be read, then the default (empty) info is returned.
+    fn load(username: &str) -> Self {
+        let path = Path::new(ACCOUNTS_SERVICE_DIR).join("users").join(username);
+        let contents = match read(&path) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default(),
+        };
+        let text = match from_utf8(contents.as_slice()) {
+            Ok(text) => text,
+            Err(err) => {
+                warn!(
+                    "AccountsService file '{}' is not UTF-8: {err}",
+                    path.display()
+                );
+                return Self::default();
+            }
+        };
+
+        // The file is a keyfile, where these are specified as: Icon=/path/to/icon
+        let icon_regex = Regex::new(r"(?m)^Icon=(.*)$").expect("Invalid regex for icon");
+        let system_account_regex =
+            Regex::new(r"(?m)^SystemAccount=(.*)$").expect("Invalid regex for system account");
+
+        let icon = icon_regex
+            .captures(text)
+            .and_then(|capture| capture.get(1))
+            .map(|icon| icon.as_str().trim())
+            .filter(|icon| !icon.is_empty())
+            .map(PathBuf::from);
+        let system_account = system_account_regex
+            .captures(text)
+            .and_then(|capture| capture.get(1))
+            .map_or(false, |value| {
+                value.as_str().trim().parse().unwrap_or(false)
+            });
+
+        Self {
+            icon,
+            system_account,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the path to a directory of test fixtures.
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn boot_entries_are_parsed_and_sorted() {
+        let entries = SysUtil::get_boot_entries(&fixture("boot-entries"));
+        let ids_titles: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.id.as_str(), entry.title.as_str()))
+            .collect();
+        assert_eq!(
+            ids_titles,
+            [
+                ("fedora-6.1.8.conf", "Fedora Linux 37 (6.1.8)"),
+                ("fedora-6.0.7.conf", "Fedora Linux 37 (6.0.7)"),
+                ("arch.conf", "Arch Linux"),
+                ("windows.conf", "Windows Boot Manager"),
+                ("untitled.conf", "untitled"),
+            ]
+        );
+    }
+
+    #[test]
+    fn versions_are_compared_by_number() {
+        assert_eq!(compare_versions("6.10.0", "6.9.12"), Ordering::Greater);
+        assert_eq!(
+            compare_versions("6.1.8-200.fc37", "6.1.8-200.fc37"),
+            Ordering::Equal
+        );
+        assert_eq!(compare_versions("6.1", "6.1.1"), Ordering::Less);
+        assert_eq!(compare_versions("6.1-rc1", "6.1-rc2"), Ordering::Less);
+    }
+
+    #[test]
+    fn missing_boot_entries_dir_has_no_entries() {
+        assert!(SysUtil::get_boot_entries(&fixture("missing")).is_empty());
+    }
+}